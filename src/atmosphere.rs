@@ -0,0 +1,97 @@
+use std::f32::consts::PI;
+use nalgebra_glm::Vec3;
+use crate::color::{Color, HdrColor};
+use crate::light::PointLight;
+use crate::math::smoothstep;
+
+/// Capa de atmósfera alrededor de un planeta, dibujada como una esfera exterior translúcida.
+#[derive(Debug, Clone, Copy)]
+pub struct Atmosphere {
+    // Grosor de la capa como fracción del radio del planeta
+    pub height: f32,
+    // Intensidad de la dispersión de Rayleigh (tiñe el cielo con `color`)
+    pub rayleigh: f32,
+    // Intensidad de la dispersión de Mie (neblina blanca alrededor del Sol)
+    pub mie: f32,
+    // Asimetría de Mie (Henyey-Greenstein): mayor que 0 dispersa hacia adelante
+    pub mie_g: f32,
+    // Color que dispersa Rayleigh; los canales más fuertes también se pierden antes cerca del terminador
+    pub color: Color,
+}
+
+impl Atmosphere {
+    pub const fn new(height: f32, rayleigh: f32, mie: f32, mie_g: f32, color: Color) -> Self {
+        Atmosphere { height, rayleigh, mie, mie_g, color }
+    }
+
+    /// Luz dispersada hacia la cámara a lo largo del rayo de vista que entra en la capa por `position`.
+    /// `center` y `outer_radius` describen la esfera exterior; `view_dir` apunta hacia la cámara.
+    pub fn scatter(&self, center: &Vec3, outer_radius: f32, position: &Vec3, view_dir: &Vec3, lights: &[PointLight]) -> HdrColor {
+        let inner_radius = outer_radius / (1.0 + self.height);
+        let ray = -view_dir;
+        let origin = position - center;
+
+        // Recorrido dentro de la capa: hasta la superficie del planeta o hasta salir de la esfera exterior
+        let b = origin.dot(&ray);
+        let discriminant = b * b - (origin.magnitude_squared() - inner_radius * inner_radius);
+        let path = if discriminant > 0.0 && -b - discriminant.sqrt() > 0.0 {
+            -b - discriminant.sqrt()
+        } else {
+            (-2.0 * b).max(0.0)
+        };
+
+        // Espesor óptico relativo al grosor de la capa: crece en el borde, donde el rayo es rasante
+        let optical_depth = path / (outer_radius - inner_radius);
+        let density = 1.0 - (-optical_depth * 0.5).exp();
+
+        // La iluminación se evalúa en el punto medio del recorrido
+        let midpoint = origin + ray * (path * 0.5);
+        let up = midpoint.normalize();
+        let beta = HdrColor::from(self.color);
+
+        let mut result = HdrColor::black();
+        for light in lights {
+            let to_light = light.position - (center + midpoint);
+            let distance = to_light.magnitude();
+            let light_dir = to_light / distance;
+            let sun_height = up.dot(&light_dir);
+
+            // La capa sigue iluminada un poco más allá del terminador
+            let daylight = smoothstep(-0.3, 0.2, sun_height);
+            if daylight <= 0.0 {
+                continue;
+            }
+
+            // Cerca del terminador la luz cruza más aire y pierde primero los canales de `color`
+            let air_mass = 1.0 / (sun_height.max(0.0) + 0.15);
+            let transmittance = HdrColor::new(
+                (-beta.r * air_mass * 0.5).exp(),
+                (-beta.g * air_mass * 0.5).exp(),
+                (-beta.b * air_mass * 0.5).exp(),
+            );
+
+            let cos_theta = ray.dot(&light_dir);
+            let rayleigh = HdrColor::new(beta.r, beta.g, beta.b) * (self.rayleigh * rayleigh_phase(cos_theta));
+            let mie = HdrColor::new(1.0, 1.0, 1.0) * (self.mie * henyey_greenstein(cos_theta, self.mie_g));
+
+            let energy = light.intensity * light.falloff.attenuation(distance) * daylight * density;
+            result = result + modulate(rayleigh + mie, transmittance) * energy;
+        }
+
+        result
+    }
+}
+
+fn rayleigh_phase(cos_theta: f32) -> f32 {
+    3.0 / (16.0 * PI) * (1.0 + cos_theta * cos_theta)
+}
+
+fn henyey_greenstein(cos_theta: f32, g: f32) -> f32 {
+    let g2 = g * g;
+    (1.0 - g2) / (4.0 * PI * (1.0 + g2 - 2.0 * g * cos_theta).powf(1.5))
+}
+
+// Producto canal a canal
+fn modulate(a: HdrColor, b: HdrColor) -> HdrColor {
+    HdrColor::new_rgba(a.r * b.r, a.g * b.g, a.b * b.b, a.a)
+}
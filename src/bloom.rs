@@ -0,0 +1,154 @@
+use crate::color::HdrColor;
+
+/// Ajustes del bloom: lo que supera `threshold` se difumina y se suma de vuelta a la imagen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BloomSettings {
+    // Luminancia a partir de la cual un píxel empieza a brillar
+    pub threshold: f32,
+    // Cuánto del brillo difuminado se suma a la imagen; 0 lo desactiva
+    pub intensity: f32,
+    // Cantidad de niveles, cada uno a la mitad de resolución del anterior
+    pub levels: usize,
+}
+
+impl Default for BloomSettings {
+    fn default() -> Self {
+        BloomSettings {
+            threshold: 1.0,
+            intensity: 1.5,
+            levels: 5,
+        }
+    }
+}
+
+// Imagen HDR auxiliar para los niveles de la cadena
+struct Level {
+    width: usize,
+    height: usize,
+    pixels: Vec<HdrColor>,
+}
+
+impl Level {
+    fn get(&self, x: isize, y: isize) -> HdrColor {
+        let x = x.clamp(0, self.width as isize - 1) as usize;
+        let y = y.clamp(0, self.height as isize - 1) as usize;
+        self.pixels[y * self.width + x]
+    }
+
+    // Muestra con filtrado bilineal; `u`, `v` en coordenadas de píxel de este nivel
+    fn sample(&self, u: f32, v: f32) -> HdrColor {
+        let (x0, y0) = (u.floor(), v.floor());
+        let (fx, fy) = (u - x0, v - y0);
+        let (x0, y0) = (x0 as isize, y0 as isize);
+
+        let top = self.get(x0, y0).lerp(&self.get(x0 + 1, y0), fx);
+        let bottom = self.get(x0, y0 + 1).lerp(&self.get(x0 + 1, y0 + 1), fx);
+        top.lerp(&bottom, fy)
+    }
+
+    // Reduce a la mitad promediando bloques de 2x2
+    fn downsample(&self) -> Level {
+        let width = (self.width / 2).max(1);
+        let height = (self.height / 2).max(1);
+        let mut pixels = Vec::with_capacity(width * height);
+
+        for y in 0..height as isize {
+            for x in 0..width as isize {
+                let sum = self.get(2 * x, 2 * y)
+                    + self.get(2 * x + 1, 2 * y)
+                    + self.get(2 * x, 2 * y + 1)
+                    + self.get(2 * x + 1, 2 * y + 1);
+                pixels.push(sum * 0.25);
+            }
+        }
+
+        Level { width, height, pixels }
+    }
+
+    // Desenfoque gaussiano separable de 5 muestras (1 4 6 4 1) / 16
+    fn blur(&self) -> Level {
+        const WEIGHTS: [f32; 5] = [1.0 / 16.0, 4.0 / 16.0, 6.0 / 16.0, 4.0 / 16.0, 1.0 / 16.0];
+
+        let pass = |source: &Level, dx: isize, dy: isize| {
+            let mut pixels = Vec::with_capacity(source.pixels.len());
+            for y in 0..source.height as isize {
+                for x in 0..source.width as isize {
+                    let sum = WEIGHTS.iter().enumerate().fold(HdrColor::black(), |sum, (i, weight)| {
+                        let offset = i as isize - 2;
+                        sum + source.get(x + offset * dx, y + offset * dy) * *weight
+                    });
+                    pixels.push(sum);
+                }
+            }
+            Level { width: source.width, height: source.height, pixels }
+        };
+
+        let horizontal = pass(self, 1, 0);
+        pass(&horizontal, 0, 1)
+    }
+
+    // Suma `other` escalado a la resolución de este nivel
+    fn add_upsampled(&mut self, other: &Level) {
+        let scale_x = other.width as f32 / self.width as f32;
+        let scale_y = other.height as f32 / self.height as f32;
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let u = (x as f32 + 0.5) * scale_x - 0.5;
+                let v = (y as f32 + 0.5) * scale_y - 0.5;
+                let index = y * self.width + x;
+                self.pixels[index] = self.pixels[index] + other.sample(u, v);
+            }
+        }
+    }
+}
+
+/// Aplica bloom sobre una imagen HDR lineal de `width` x `height`.
+pub fn apply(image: &mut [HdrColor], width: usize, height: usize, settings: &BloomSettings) {
+    if settings.intensity <= 0.0 || settings.levels == 0 || width == 0 || height == 0 {
+        return;
+    }
+
+    // Se extrae solo lo que supera el umbral, conservando el tono del color
+    let bright = Level {
+        width,
+        height,
+        pixels: image.iter()
+            .map(|color| {
+                let luminance = color.luminance();
+                if luminance <= settings.threshold {
+                    HdrColor::black()
+                } else {
+                    *color * ((luminance - settings.threshold) / luminance)
+                }
+            })
+            .collect(),
+    };
+
+    // Cadena de niveles a resoluciones decrecientes, cada uno difuminado
+    let mut levels: Vec<Level> = Vec::with_capacity(settings.levels);
+    let mut current = bright.downsample();
+    for _ in 0..settings.levels {
+        let next = current.downsample();
+        levels.push(current.blur());
+        if next.width == current.width && next.height == current.height {
+            break;
+        }
+        current = next;
+    }
+
+    // Se acumula de la resolución más baja a la más alta
+    let mut accumulated = levels.pop().expect("at least one bloom level");
+    while let Some(mut level) = levels.pop() {
+        level.add_upsampled(&accumulated);
+        accumulated = level;
+    }
+
+    let mut full = Level { width, height, pixels: vec![HdrColor::black(); width * height] };
+    full.add_upsampled(&accumulated);
+
+    let strength = settings.intensity / settings.levels as f32;
+    for (pixel, glow) in image.iter_mut().zip(full.pixels.iter()) {
+        *pixel = *pixel + *glow * strength;
+    }
+}
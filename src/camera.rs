@@ -1,9 +1,20 @@
-use nalgebra_glm::{Vec3, Mat4};
+use nalgebra_glm::{Vec3, Vec4, Mat4};
+
+/// Tipo de proyección de una matriz, para saber cómo llegan los rayos de vista sin leer sus coeficientes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    // Rayos que parten de la posición de la cámara
+    Perspective,
+    // Rayos paralelos a la dirección de la cámara
+    Orthographic,
+}
 
 pub struct Camera {
     pub translation: Vec3,
     pub rotation: Vec3,
     pub scale: f32,
+    pub near: f32,
+    pub far: f32,
 }
 
 impl Camera {
@@ -12,6 +23,8 @@ impl Camera {
             translation: Vec3::new(0.0, 0.0, -500.0),
             rotation: Vec3::new(0.0, 0.0, 0.0),
             scale: 1.0,
+            near: -5000.0,
+            far: 5000.0,
         }
     }
 
@@ -26,4 +39,39 @@ impl Camera {
 
         scaling_matrix * rotation_matrix * translation_matrix
     }
+
+    /// Posición de la cámara en espacio de mundo.
+    pub fn position(&self) -> Vec3 {
+        let inverse_view = self.view_matrix().try_inverse().unwrap_or_else(Mat4::identity);
+        (inverse_view * Vec4::new(0.0, 0.0, 0.0, 1.0)).xyz()
+    }
+
+    /// Dirección unitaria hacia la que mira la cámara (+z de vista), en espacio de mundo.
+    pub fn direction(&self) -> Vec3 {
+        let inverse_view = self.view_matrix().try_inverse().unwrap_or_else(Mat4::identity);
+        (inverse_view * Vec4::new(0.0, 0.0, 1.0, 0.0)).xyz().normalize()
+    }
+
+    pub fn projection(&self) -> Projection {
+        Projection::Orthographic
+    }
+
+    // Proyección ortográfica en espacio de píxeles: z crece hacia dentro de la pantalla
+    pub fn projection_matrix(&self, width: usize, height: usize) -> Mat4 {
+        nalgebra_glm::ortho_lh_no(0.0, width as f32, 0.0, height as f32, self.near, self.far)
+    }
+
+    // Inversa de la proyección: devuelve x, y en píxeles y z en las mismas unidades de profundidad
+    pub fn viewport_matrix(&self, width: usize, height: usize) -> Mat4 {
+        let half_width = width as f32 / 2.0;
+        let half_height = height as f32 / 2.0;
+        let half_depth = (self.far - self.near) / 2.0;
+
+        Mat4::new(
+            half_width, 0.0, 0.0, half_width,
+            0.0, half_height, 0.0, half_height,
+            0.0, 0.0, half_depth, self.near + half_depth,
+            0.0, 0.0, 0.0, 1.0,
+        )
+    }
 }
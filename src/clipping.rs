@@ -0,0 +1,187 @@
+use nalgebra_glm::{Vec3, Vec4, Mat4};
+use crate::vertex::Vertex;
+
+// Evita dividir por `w` cercanos a cero aunque la proyección no tenga plano cercano útil
+const W_EPSILON: f32 = 1e-5;
+
+#[derive(Debug, Clone, Copy)]
+enum ClipPlane {
+    W,
+    Left,
+    Right,
+    Bottom,
+    Top,
+    Near,
+    Far,
+}
+
+const CLIP_PLANES: [ClipPlane; 7] = [
+    ClipPlane::W,
+    ClipPlane::Near,
+    ClipPlane::Far,
+    ClipPlane::Left,
+    ClipPlane::Right,
+    ClipPlane::Bottom,
+    ClipPlane::Top,
+];
+
+impl ClipPlane {
+    // Distancia con signo al plano en espacio de clip; positiva significa "dentro"
+    fn distance(&self, p: &Vec4) -> f32 {
+        match self {
+            ClipPlane::W => p.w - W_EPSILON,
+            ClipPlane::Left => p.w + p.x,
+            ClipPlane::Right => p.w - p.x,
+            ClipPlane::Bottom => p.w + p.y,
+            ClipPlane::Top => p.w - p.y,
+            ClipPlane::Near => p.w + p.z,
+            ClipPlane::Far => p.w - p.z,
+        }
+    }
+}
+
+/// Recorta un triángulo contra el frustum (-w <= x, y, z <= w) antes de la división por `w`.
+/// Devuelve los triángulos resultantes con sus atributos interpolados.
+pub fn clip_triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> Vec<[Vertex; 3]> {
+    let vertices = [v1, v2, v3];
+    let mut fully_inside = true;
+
+    for plane in CLIP_PLANES {
+        let distances = vertices.map(|v| plane.distance(&v.clip_position));
+        if distances.iter().all(|&d| d < 0.0) {
+            return Vec::new();
+        }
+        if distances.iter().any(|&d| d < 0.0) {
+            fully_inside = false;
+        }
+    }
+
+    if fully_inside {
+        return vec![[v1.clone(), v2.clone(), v3.clone()]];
+    }
+
+    let mut polygon = vec![v1.clone(), v2.clone(), v3.clone()];
+    for plane in CLIP_PLANES {
+        polygon = clip_polygon(&polygon, plane);
+        if polygon.len() < 3 {
+            return Vec::new();
+        }
+    }
+
+    // Triangulación en abanico del polígono recortado
+    (1..polygon.len() - 1)
+        .map(|i| [polygon[0].clone(), polygon[i].clone(), polygon[i + 1].clone()])
+        .collect()
+}
+
+// Sutherland-Hodgman contra un solo plano
+fn clip_polygon(polygon: &[Vertex], plane: ClipPlane) -> Vec<Vertex> {
+    let mut output = Vec::with_capacity(polygon.len() + 1);
+
+    for i in 0..polygon.len() {
+        let current = &polygon[i];
+        let next = &polygon[(i + 1) % polygon.len()];
+        let d_current = plane.distance(&current.clip_position);
+        let d_next = plane.distance(&next.clip_position);
+
+        if d_current >= 0.0 {
+            output.push(current.clone());
+        }
+        // Solo hay cruce si los vértices quedan en lados opuestos; uno sobre el plano ya se agregó tal cual
+        if (d_current > 0.0 && d_next < 0.0) || (d_current < 0.0 && d_next > 0.0) {
+            let t = d_current / (d_current - d_next);
+            output.push(current.lerp(next, t));
+        }
+    }
+
+    output
+}
+
+/// División por `w` y transformación de viewport de un vértice ya recortado.
+pub fn project_to_screen(vertex: &Vertex, viewport_matrix: &Mat4) -> Vertex {
+    let clip = vertex.clip_position;
+    let ndc = Vec4::new(clip.x / clip.w, clip.y / clip.w, clip.z / clip.w, 1.0);
+    let screen = viewport_matrix * ndc;
+
+    Vertex {
+        transformed_position: Vec3::new(screen.x, screen.y, screen.z),
+        ..vertex.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::Vec2;
+
+    fn clip_vertex(x: f32, y: f32, z: f32, w: f32) -> Vertex {
+        Vertex {
+            clip_position: Vec4::new(x, y, z, w),
+            ..Vertex::new(Vec3::new(x, y, z), Vec3::new(0.0, 0.0, 1.0), Vec2::new(0.0, 0.0))
+        }
+    }
+
+    fn clip(points: [(f32, f32, f32, f32); 3]) -> Vec<[Vertex; 3]> {
+        let [a, b, c] = points.map(|(x, y, z, w)| clip_vertex(x, y, z, w));
+        clip_triangle(&a, &b, &c)
+    }
+
+    // Todos los vértices de la salida quedan dentro de los 7 planos, salvo el error de la interpolación
+    fn assert_inside_all_planes(triangles: &[[Vertex; 3]]) {
+        for vertex in triangles.iter().flatten() {
+            for plane in CLIP_PLANES {
+                let distance = plane.distance(&vertex.clip_position);
+                assert!(distance >= -1e-5, "{:?} is outside {:?} by {}", vertex.clip_position, plane, distance);
+            }
+        }
+    }
+
+    #[test]
+    fn keeps_triangles_fully_inside() {
+        let points = [(-0.5, -0.5, 0.0, 1.0), (0.5, -0.5, 0.2, 1.0), (0.0, 0.5, -0.2, 2.0)];
+        let triangles = clip(points);
+
+        assert_eq!(triangles.len(), 1);
+        for (vertex, (x, y, z, w)) in triangles[0].iter().zip(points) {
+            assert_eq!(vertex.clip_position, Vec4::new(x, y, z, w));
+        }
+    }
+
+    #[test]
+    fn drops_triangles_fully_outside() {
+        assert!(clip([(2.0, 0.0, 0.0, 1.0), (3.0, 0.0, 0.0, 1.0), (2.0, 0.5, 0.0, 1.0)]).is_empty());
+        assert!(clip([(0.0, 0.0, 0.0, -1.0), (0.5, 0.0, 0.0, 0.0), (0.0, 0.5, 0.0, -2.0)]).is_empty());
+    }
+
+    #[test]
+    fn clips_vertex_behind_the_camera() {
+        // El tercer vértice tiene w < 0: el plano W deja un cuadrilátero y, como cerca de w = 0 el
+        // frustum se angosta, el plano derecho le corta una esquina y quedan 5 vértices
+        let triangles = clip([(0.0, 0.0, 0.0, 1.0), (0.5, 0.0, 0.0, 1.0), (0.0, 0.0, 0.0, -1.0)]);
+
+        assert_eq!(triangles.len(), 3);
+        assert_inside_all_planes(&triangles);
+        assert!(triangles.iter().flatten().all(|vertex| vertex.clip_position.w >= W_EPSILON * 0.99));
+
+        let on_plane = triangles.iter().flatten().filter(|vertex| (vertex.clip_position.w - W_EPSILON).abs() < 1e-6);
+        assert!(on_plane.count() >= 2);
+    }
+
+    #[test]
+    fn clips_triangle_straddling_two_planes() {
+        // Cruza los planos derecho (x = w) y superior (y = w): queda el cuadrado de (0, 0) a (1, 1)
+        let triangles = clip([(0.0, 0.0, 0.0, 1.0), (2.0, 0.0, 0.0, 1.0), (0.0, 2.0, 0.0, 1.0)]);
+
+        assert_eq!(triangles.len(), 2);
+        assert_eq!(triangles.iter().flatten().count(), 6);
+        assert_inside_all_planes(&triangles);
+
+        let mut corners: Vec<(f32, f32)> = triangles.iter()
+            .flatten()
+            .map(|vertex| (vertex.clip_position.x, vertex.clip_position.y))
+            .collect();
+        corners.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        corners.dedup();
+        assert_eq!(corners, [(0.0, 0.0), (0.0, 1.0), (1.0, 0.0), (1.0, 1.0)]);
+    }
+}
@@ -1,28 +1,37 @@
 use std::fmt;
 use std::ops::Mul;
 use std::ops::Add;
+use crate::math::smoothstep;
 
 #[derive(Debug, Clone, Copy)]
 pub struct Color {
     r: u8,
     g: u8,
     b: u8,
+    a: u8,
 }
 
 impl Color {
     pub const fn new(r: u8, g: u8, b: u8) -> Self {
-        Color { r, g, b }
+        Color { r, g, b, a: 255 }
+    }
+    pub const fn new_rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Color { r, g, b, a }
     }
     pub const fn from_hex(hex: u32) -> Self {
         let r = ((hex >> 16) & 0xFF) as u8;
         let g = ((hex >> 8) & 0xFF) as u8;
         let b = (hex & 0xFF) as u8;
-        Color { r, g, b }
+        Color { r, g, b, a: 255 }
     }
     pub const fn black() -> Self {
-        Color { r: 0, g: 0, b: 0 }
+        Color { r: 0, g: 0, b: 0, a: 255 }
+    }
+    pub const fn white() -> Self {
+        Color { r: 255, g: 255, b: 255, a: 255 }
     }
-    pub const fn to_hex(&self) -> u32 {
+    // El framebuffer guarda 0RGB; el alfa solo se usa al mezclar
+    pub const fn to_hex(self) -> u32 {
         ((self.r as u32) << 16) | ((self.g as u32) << 8) | (self.b as u32)
     }
     pub fn r(&self) -> u8 {
@@ -37,12 +46,25 @@ impl Color {
         self.b
     }
 
+    pub fn a(&self) -> u8 {
+        self.a
+    }
+
+    pub fn alpha(&self) -> f32 {
+        self.a as f32 / 255.0
+    }
+
+    pub const fn with_alpha(self, a: u8) -> Self {
+        Color { a, ..self }
+    }
+
     pub fn lerp(&self, other: &Color, t: f32) -> Self {
         let t = t.clamp(0.0, 1.0);
         Color {
             r: (self.r as f32 + (other.r as f32 - self.r as f32) * t).round() as u8,
             g: (self.g as f32 + (other.g as f32 - self.g as f32) * t).round() as u8,
             b: (self.b as f32 + (other.b as f32 - self.b as f32) * t).round() as u8,
+            a: (self.a as f32 + (other.a as f32 - self.a as f32) * t).round() as u8,
         }
     }
 
@@ -55,36 +77,51 @@ impl Color {
     }
     
     pub fn blend_multiply(&self, blend: &Color) -> Color {
-        Color::new(
+        Color::new_rgba(
             ((self.r as f32 * blend.r as f32) / 255.0) as u8,
             ((self.g as f32 * blend.g as f32) / 255.0) as u8,
-            ((self.b as f32 * blend.b as f32) / 255.0) as u8
+            ((self.b as f32 * blend.b as f32) / 255.0) as u8,
+            self.a
         )
     }
     
     pub fn blend_add(&self, blend: &Color) -> Color {
-        Color::new(
+        Color::new_rgba(
             (self.r as u16 + blend.r as u16).min(255) as u8,
             (self.g as u16 + blend.g as u16).min(255) as u8,
-            (self.b as u16 + blend.b as u16).min(255) as u8
+            (self.b as u16 + blend.b as u16).min(255) as u8,
+            self.a
         )
     }
     
     pub fn blend_subtract(&self, blend: &Color) -> Color {
-        Color::new(
+        Color::new_rgba(
             self.r.saturating_sub(blend.r),
             self.g.saturating_sub(blend.g),
-            self.b.saturating_sub(blend.b)
+            self.b.saturating_sub(blend.b),
+            self.a
         )
     }
     
     pub fn blend_screen(&self, blend: &Color) -> Color {
-        Color::new(
+        Color::new_rgba(
             255 - ((255 - self.r as u16) * (255 - blend.r as u16) / 255) as u8,
             255 - ((255 - self.g as u16) * (255 - blend.g as u16) / 255) as u8,
-            255 - ((255 - self.b as u16) * (255 - blend.b as u16) / 255) as u8
+            255 - ((255 - self.b as u16) * (255 - blend.b as u16) / 255) as u8,
+            self.a
         )
-    }    
+    }
+
+    // Composición "over": `self` (con su alfa) sobre el color de fondo `dst`
+    pub fn over(&self, dst: &Color) -> Color {
+        let alpha = self.alpha();
+        Color::new_rgba(
+            (self.r as f32 * alpha + dst.r as f32 * (1.0 - alpha)).round() as u8,
+            (self.g as f32 * alpha + dst.g as f32 * (1.0 - alpha)).round() as u8,
+            (self.b as f32 * alpha + dst.b as f32 * (1.0 - alpha)).round() as u8,
+            dst.a
+        )
+    }
 }
 
 impl Add for Color {
@@ -95,6 +132,7 @@ impl Add for Color {
             r: self.r.saturating_add(other.r),
             g: self.g.saturating_add(other.g),
             b: self.b.saturating_add(other.b),
+            a: self.a.max(other.a),
         }
     }
 }
@@ -108,12 +146,423 @@ impl Mul<f32> for Color {
             r: (self.r as f32 * scalar).clamp(0.0, 255.0) as u8,
             g: (self.g as f32 * scalar).clamp(0.0, 255.0) as u8,
             b: (self.b as f32 * scalar).clamp(0.0, 255.0) as u8,
+            a: self.a,
         }
     }
 }
 
 impl fmt::Display for Color {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Color(r: {}, g: {}, b: {})", self.r, self.g, self.b)
+        write!(f, "Color(r: {}, g: {}, b: {}, a: {})", self.r, self.g, self.b, self.a)
+    }
+}
+
+/// Cómo se pasa de una parada a la siguiente en un `Gradient`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interpolation {
+    Linear,
+    // Suaviza la entrada y la salida de cada tramo
+    Smoothstep,
+    // Mantiene el color de la parada anterior hasta llegar a la siguiente
+    Constant,
+}
+
+impl Interpolation {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "linear" => Some(Interpolation::Linear),
+            "smoothstep" => Some(Interpolation::Smoothstep),
+            "constant" => Some(Interpolation::Constant),
+            _ => None,
+        }
+    }
+}
+
+/// Espacio de color en que se mezclan dos paradas de un `Gradient`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorSpace {
+    Rgb,
+    // El tono gira por el camino más corto del círculo cromático
+    Hsv,
+    // Espacio perceptual: los tramos intermedios no se oscurecen ni se agrisan
+    Oklab,
+}
+
+impl ColorSpace {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "rgb" => Some(ColorSpace::Rgb),
+            "hsv" => Some(ColorSpace::Hsv),
+            "oklab" => Some(ColorSpace::Oklab),
+            _ => None,
+        }
+    }
+
+    /// Mezcla `from` y `to` en este espacio; `t` va de 0 (`from`) a 1 (`to`). El alfa se mezcla en línea recta.
+    pub fn mix(&self, from: &Color, to: &Color, t: f32) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        let alpha = (from.a as f32 + (to.a as f32 - from.a as f32) * t).round() as u8;
+        match self {
+            ColorSpace::Rgb => from.lerp(to, t),
+            ColorSpace::Hsv => {
+                let (h0, s0, v0) = rgb_to_hsv(from);
+                let (h1, s1, v1) = rgb_to_hsv(to);
+                // Grises y blanco no tienen tono, y el negro tampoco saturación: toman los del otro extremo
+                // para que, p. ej., de negro a azul no se pase por rojos y magentas
+                let (h0, s0) = if v0 == 0.0 { (h1, s1) } else if s0 == 0.0 { (h1, s0) } else { (h0, s0) };
+                let (h1, s1) = if v1 == 0.0 { (h0, s0) } else if s1 == 0.0 { (h0, s1) } else { (h1, s1) };
+                // Diferencia de tono en [-180, 180)
+                let dh = (h1 - h0 + 540.0) % 360.0 - 180.0;
+                let hue = (h0 + dh * t + 360.0) % 360.0;
+                hsv_to_rgb(hue, s0 + (s1 - s0) * t, v0 + (v1 - v0) * t).with_alpha(alpha)
+            }
+            ColorSpace::Oklab => {
+                let a = rgb_to_oklab(from);
+                let b = rgb_to_oklab(to);
+                let mixed = [
+                    a[0] + (b[0] - a[0]) * t,
+                    a[1] + (b[1] - a[1]) * t,
+                    a[2] + (b[2] - a[2]) * t,
+                ];
+                oklab_to_rgb(mixed).with_alpha(alpha)
+            }
+        }
+    }
+}
+
+/// Rampa de colores: paradas en posiciones arbitrarias que se muestrean con un escalar.
+/// Antes de la primera parada y después de la última se repite su color.
+#[derive(Debug, Clone)]
+pub struct Gradient {
+    // Ordenadas por posición
+    stops: Vec<(f32, Color)>,
+    pub interpolation: Interpolation,
+    pub space: ColorSpace,
+}
+
+impl Gradient {
+    pub fn new(mut stops: Vec<(f32, Color)>) -> Self {
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Gradient {
+            stops,
+            interpolation: Interpolation::Linear,
+            space: ColorSpace::Rgb,
+        }
+    }
+
+    /// Paradas repartidas a la par entre 0 y 1, en el orden de `colors`.
+    pub fn evenly_spaced(colors: &[Color]) -> Self {
+        let last = colors.len().saturating_sub(1).max(1) as f32;
+        Gradient::new(colors.iter().enumerate().map(|(i, color)| (i as f32 / last, *color)).collect())
+    }
+
+    pub fn with_interpolation(self, interpolation: Interpolation) -> Self {
+        Gradient { interpolation, ..self }
+    }
+
+    pub fn with_space(self, space: ColorSpace) -> Self {
+        Gradient { space, ..self }
+    }
+
+    pub fn stops(&self) -> &[(f32, Color)] {
+        &self.stops
     }
-}
\ No newline at end of file
+
+    pub fn sample(&self, t: f32) -> Color {
+        let (first, last) = match (self.stops.first(), self.stops.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Color::black(),
+        };
+        if t.is_nan() || t <= first.0 {
+            return first.1;
+        }
+        if t >= last.0 {
+            return last.1;
+        }
+
+        // Primera parada después de `t`; existe porque `t` está antes de la última
+        let next = self.stops.partition_point(|stop| stop.0 <= t);
+        let (p0, c0) = self.stops[next - 1];
+        let (p1, c1) = self.stops[next];
+        let local = (t - p0) / (p1 - p0);
+        let local = match self.interpolation {
+            Interpolation::Linear => local,
+            Interpolation::Smoothstep => smoothstep(0.0, 1.0, local),
+            Interpolation::Constant => 0.0,
+        };
+        self.space.mix(&c0, &c1, local)
+    }
+}
+
+// Tono en grados [0, 360), saturación y valor en [0, 1]
+fn rgb_to_hsv(color: &Color) -> (f32, f32, f32) {
+    let r = color.r as f32 / 255.0;
+    let g = color.g as f32 / 255.0;
+    let b = color.b as f32 / 255.0;
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    let saturation = if max == 0.0 { 0.0 } else { delta / max };
+    (hue, saturation, max)
+}
+
+fn hsv_to_rgb(hue: f32, saturation: f32, value: f32) -> Color {
+    let c = value * saturation;
+    let x = c * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+    let m = value - c;
+    let (r, g, b) = match (hue / 60.0) as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let channel = |v: f32| ((v + m).clamp(0.0, 1.0) * 255.0).round() as u8;
+    Color::new(channel(r), channel(g), channel(b))
+}
+
+// Oklab parte de RGB lineal: los 8 bits se decodifican como sRGB, que es como se eligen los colores
+fn srgb_to_linear(channel: u8) -> f32 {
+    let c = channel as f32 / 255.0;
+    if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+}
+
+fn linear_to_srgb(value: f32) -> u8 {
+    let v = value.clamp(0.0, 1.0);
+    let c = if v <= 0.0031308 { v * 12.92 } else { 1.055 * v.powf(1.0 / 2.4) - 0.055 };
+    (c * 255.0).round() as u8
+}
+
+fn rgb_to_oklab(color: &Color) -> [f32; 3] {
+    let r = srgb_to_linear(color.r);
+    let g = srgb_to_linear(color.g);
+    let b = srgb_to_linear(color.b);
+
+    let l = (0.41222146 * r + 0.53633255 * g + 0.051445995 * b).cbrt();
+    let m = (0.2119035 * r + 0.6806995 * g + 0.10739696 * b).cbrt();
+    let s = (0.08830246 * r + 0.28171885 * g + 0.6299787 * b).cbrt();
+
+    [
+        0.21045426 * l + 0.7936178 * m - 0.004072047 * s,
+        1.9779985 * l - 2.4285922 * m + 0.4505937 * s,
+        0.025904037 * l + 0.78277177 * m - 0.80867577 * s,
+    ]
+}
+
+fn oklab_to_rgb(lab: [f32; 3]) -> Color {
+    let l = (lab[0] + 0.39633778 * lab[1] + 0.21580376 * lab[2]).powi(3);
+    let m = (lab[0] - 0.105561346 * lab[1] - 0.06385417 * lab[2]).powi(3);
+    let s = (lab[0] - 0.08948418 * lab[1] - 1.2914855 * lab[2]).powi(3);
+
+    Color::new(
+        linear_to_srgb(4.0767417 * l - 3.3077116 * m + 0.23096994 * s),
+        linear_to_srgb(-1.268438 * l + 2.6097574 * m - 0.34131938 * s),
+        linear_to_srgb(-0.0041960864 * l - 0.7034186 * m + 1.7076147 * s),
+    )
+}
+
+/// Color de alto rango dinámico en radiancia lineal, sin límite superior por canal.
+/// Los colores de 8 bits se interpretan como radiancia lineal en [0, 1].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HdrColor {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl HdrColor {
+    pub const fn new(r: f32, g: f32, b: f32) -> Self {
+        HdrColor { r, g, b, a: 1.0 }
+    }
+
+    pub const fn new_rgba(r: f32, g: f32, b: f32, a: f32) -> Self {
+        HdrColor { r, g, b, a }
+    }
+
+    pub const fn black() -> Self {
+        HdrColor { r: 0.0, g: 0.0, b: 0.0, a: 1.0 }
+    }
+
+    pub const fn with_alpha(self, a: f32) -> Self {
+        HdrColor { a, ..self }
+    }
+
+    pub fn luminance(&self) -> f32 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+
+    pub fn lerp(&self, other: &HdrColor, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        HdrColor {
+            r: self.r + (other.r - self.r) * t,
+            g: self.g + (other.g - self.g) * t,
+            b: self.b + (other.b - self.b) * t,
+            a: self.a + (other.a - self.a) * t,
+        }
+    }
+
+    // Recorta a [0, 1] y cuantiza a 8 bits
+    pub fn to_color(self) -> Color {
+        Color::new_rgba(
+            (self.r.clamp(0.0, 1.0) * 255.0).round() as u8,
+            (self.g.clamp(0.0, 1.0) * 255.0).round() as u8,
+            (self.b.clamp(0.0, 1.0) * 255.0).round() as u8,
+            (self.a.clamp(0.0, 1.0) * 255.0).round() as u8,
+        )
+    }
+}
+
+impl From<Color> for HdrColor {
+    fn from(color: Color) -> Self {
+        HdrColor {
+            r: color.r as f32 / 255.0,
+            g: color.g as f32 / 255.0,
+            b: color.b as f32 / 255.0,
+            a: color.a as f32 / 255.0,
+        }
+    }
+}
+
+impl Add for HdrColor {
+    type Output = HdrColor;
+
+    fn add(self, other: HdrColor) -> HdrColor {
+        HdrColor {
+            r: self.r + other.r,
+            g: self.g + other.g,
+            b: self.b + other.b,
+            a: self.a.max(other.a),
+        }
+    }
+}
+
+impl Mul<f32> for HdrColor {
+    type Output = HdrColor;
+
+    fn mul(self, scalar: f32) -> HdrColor {
+        HdrColor {
+            r: self.r * scalar,
+            g: self.g * scalar,
+            b: self.b * scalar,
+            a: self.a,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgb(color: Color) -> (u8, u8, u8) {
+        (color.r, color.g, color.b)
+    }
+
+    const RED: Color = Color::new(255, 0, 0);
+    const BLUE: Color = Color::new(0, 0, 255);
+
+    #[test]
+    fn rgb_mix_is_a_straight_line() {
+        assert_eq!(rgb(ColorSpace::Rgb.mix(&Color::black(), &Color::white(), 0.5)), (128, 128, 128));
+        assert_eq!(rgb(ColorSpace::Rgb.mix(&RED, &BLUE, 0.25)), (191, 0, 64));
+    }
+
+    #[test]
+    fn mix_clamps_t_and_blends_alpha() {
+        for space in [ColorSpace::Rgb, ColorSpace::Hsv, ColorSpace::Oklab] {
+            assert_eq!(rgb(space.mix(&RED, &BLUE, -1.0)), rgb(RED), "{:?}", space);
+            assert_eq!(rgb(space.mix(&RED, &BLUE, 2.0)), rgb(BLUE), "{:?}", space);
+            assert_eq!(space.mix(&RED.with_alpha(0), &BLUE, 0.5).a, 128, "{:?}", space);
+        }
+    }
+
+    #[test]
+    fn hsv_mix_takes_the_shortest_hue_path() {
+        // Rojo (0°) a azul (240°) pasa por magenta (300°), no por verde
+        assert_eq!(rgb(ColorSpace::Hsv.mix(&RED, &BLUE, 0.5)), (255, 0, 255));
+        assert_eq!(rgb(ColorSpace::Hsv.mix(&BLUE, &RED, 0.5)), (255, 0, 255));
+    }
+
+    #[test]
+    fn hsv_mix_borrows_hue_for_achromatic_stops() {
+        assert_eq!(rgb(ColorSpace::Hsv.mix(&Color::black(), &BLUE, 0.5)), (0, 0, 128));
+        assert_eq!(rgb(ColorSpace::Hsv.mix(&BLUE, &Color::black(), 0.5)), (0, 0, 128));
+        assert_eq!(rgb(ColorSpace::Hsv.mix(&Color::white(), &RED, 0.5)), (255, 128, 128));
+        assert_eq!(rgb(ColorSpace::Hsv.mix(&Color::new(128, 128, 128), &BLUE, 0.5)), (96, 96, 192));
+    }
+
+    #[test]
+    fn oklab_mix_is_perceptually_even() {
+        // A mitad de camino la luminosidad perceptual es 0.5, más oscuro que el gris de la mezcla en RGB
+        assert_eq!(rgb(ColorSpace::Oklab.mix(&Color::black(), &Color::white(), 0.5)), (99, 99, 99));
+    }
+
+    #[test]
+    fn oklab_round_trips() {
+        for color in [RED, Color::new(0, 255, 0), BLUE, Color::new(12, 200, 99), Color::white()] {
+            assert_eq!(rgb(oklab_to_rgb(rgb_to_oklab(&color))), rgb(color));
+        }
+    }
+
+    #[test]
+    fn empty_gradient_is_black() {
+        assert_eq!(rgb(Gradient::new(Vec::new()).sample(0.5)), (0, 0, 0));
+    }
+
+    #[test]
+    fn gradient_repeats_end_stops() {
+        let gradient = Gradient::new(vec![(0.75, BLUE), (0.25, RED)]);
+        assert_eq!(rgb(gradient.sample(0.0)), rgb(RED));
+        assert_eq!(rgb(gradient.sample(0.25)), rgb(RED));
+        assert_eq!(rgb(gradient.sample(1.0)), rgb(BLUE));
+        assert_eq!(rgb(gradient.sample(f32::NAN)), rgb(RED));
+        assert_eq!(rgb(gradient.sample(0.5)), (128, 0, 128));
+
+        let single = Gradient::new(vec![(0.5, BLUE)]);
+        assert_eq!(rgb(single.sample(0.0)), rgb(BLUE));
+        assert_eq!(rgb(single.sample(1.0)), rgb(BLUE));
+    }
+
+    #[test]
+    fn gradient_interpolation_modes() {
+        let gradient = Gradient::new(vec![(0.0, Color::black()), (0.5, Color::white()), (1.0, RED)]);
+        assert_eq!(rgb(gradient.sample(0.125)), (64, 64, 64));
+
+        let smooth = gradient.clone().with_interpolation(Interpolation::Smoothstep);
+        // smoothstep(0.25) = 0.15625
+        assert_eq!(rgb(smooth.sample(0.125)), (40, 40, 40));
+        assert_eq!(rgb(smooth.sample(0.5)), (255, 255, 255));
+
+        let constant = gradient.with_interpolation(Interpolation::Constant);
+        assert_eq!(rgb(constant.sample(0.49)), (0, 0, 0));
+        assert_eq!(rgb(constant.sample(0.5)), (255, 255, 255));
+        assert_eq!(rgb(constant.sample(0.99)), (255, 255, 255));
+    }
+
+    #[test]
+    fn evenly_spaced_gradient() {
+        let gradient = Gradient::evenly_spaced(&[RED, Color::white(), BLUE]);
+        assert_eq!(rgb(gradient.sample(0.5)), (255, 255, 255));
+        assert_eq!(rgb(gradient.sample(0.75)), (128, 128, 255));
+        assert_eq!(rgb(Gradient::evenly_spaced(&[BLUE]).sample(0.3)), rgb(BLUE));
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(ColorSpace::from_name("oklab"), Some(ColorSpace::Oklab));
+        assert_eq!(ColorSpace::from_name("OKLAB"), None);
+        assert_eq!(Interpolation::from_name("constant"), Some(Interpolation::Constant));
+        assert_eq!(Interpolation::from_name("cubic"), None);
+    }
+}
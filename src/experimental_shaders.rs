@@ -1,11 +1,17 @@
-use nalgebra_glm::{Vec2, Vec3};
 use std::f32::consts::PI;
-use crate::color::Color;
+use crate::color::{Color, HdrColor};
+use crate::shader_registry::{FragmentShader, ShaderRegistry, Unlit};
 use crate::Uniforms;
 use rand::prelude::*;
 use rand::rngs::StdRng;
 use rand::SeedableRng;
 use crate::fragment::Fragment;
+use crate::light::diffuse_intensity;
+
+// Iluminación difusa con sombras: estos shaders la calculan por su cuenta porque no pasan por `Material`
+fn lit_intensity(fragment: &Fragment, uniforms: &Uniforms) -> f32 {
+    diffuse_intensity(&uniforms.lights, &fragment.world_position, &fragment.world_normal) * fragment.visibility
+}
 
 fn static_pattern_shader(fragment: &Fragment) -> Color {
     let x = fragment.vertex_position.x;
@@ -50,9 +56,9 @@ pub fn combined_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
 
     // Usa el color del círculo si no es negro, sino usa el color base
     if !circle_color.is_black() {
-        circle_color * fragment.intensity
+        circle_color * lit_intensity(fragment, uniforms)
     } else {
-        base_color * fragment.intensity
+        base_color * lit_intensity(fragment, uniforms)
     }
 }
 
@@ -72,7 +78,7 @@ fn circle_shader(fragment: &Fragment) -> Color {
     }
 }
 
-pub fn combined_blend_shader(fragment: &Fragment, blend_mode: &str) -> Color {
+pub fn combined_blend_shader(fragment: &Fragment, uniforms: &Uniforms, blend_mode: &str) -> Color {
     let base_color = purple_shader(fragment);
     let circle_color = circle_shader(fragment);
 
@@ -84,7 +90,25 @@ pub fn combined_blend_shader(fragment: &Fragment, blend_mode: &str) -> Color {
         _ => base_color
     };
 
-    combined_color * fragment.intensity
+    combined_color * lit_intensity(fragment, uniforms)
+}
+
+/// Disco amarillo sobre fondo morado, combinados con el modo de mezcla `blend_mode`
+/// ("normal", "multiply", "add" o "subtract").
+pub struct CombinedBlend {
+    pub blend_mode: String,
+}
+
+impl Default for CombinedBlend {
+    fn default() -> Self {
+        CombinedBlend { blend_mode: "normal".to_string() }
+    }
+}
+
+impl FragmentShader for CombinedBlend {
+    fn shade(&self, fragment: &Fragment, uniforms: &Uniforms) -> HdrColor {
+        combined_blend_shader(fragment, uniforms, &self.blend_mode).into()
+    }
 }
 
 fn glow_shader(fragment: &Fragment) -> Color {
@@ -128,61 +152,101 @@ pub fn random_color_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     let r = rng.gen_range(0..=255);
     let g = rng.gen_range(0..=255);
     let b = rng.gen_range(0..=255);
-    Color::new(r, g, b) * fragment.intensity
+    Color::new(r, g, b) * lit_intensity(fragment, uniforms)
 }
 
-pub fn panda_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let zoom = 50.0;
-    let x = fragment.vertex_position.x;
-    let y = fragment.vertex_position.y;
-    let noise_value = uniforms.noise_open_simplex.get_noise_2d(x * zoom, y * zoom);
-    let spot_threshold = 0.5;
-    let spot_color = Color::new(255, 255, 255);
-    let base_color = Color::new(0, 0, 0);
+/// Manchas blancas sobre negro donde el ruido queda por debajo de `spot_threshold`.
+pub struct Panda {
+    pub zoom: f32,
+    pub spot_threshold: f32,
+}
 
-    (if noise_value < spot_threshold { spot_color } else { base_color }) * fragment.intensity
+impl Default for Panda {
+    fn default() -> Self {
+        Panda { zoom: 50.0, spot_threshold: 0.5 }
+    }
 }
 
-pub fn cloud_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let zoom = 100.0;
-    let x = fragment.vertex_position.x;
-    let y = fragment.vertex_position.y;
-    let t = uniforms.time as f32 * 0.5;
-    let noise_value = uniforms.noise_open_simplex.get_noise_2d(x * zoom + t, y * zoom);
-    let cloud_threshold = 0.5;
-    let cloud_color = Color::new(255, 255, 255);
-    let sky_color = Color::new(30, 97, 145);
+impl FragmentShader for Panda {
+    fn shade(&self, fragment: &Fragment, uniforms: &Uniforms) -> HdrColor {
+        let x = fragment.vertex_position.x;
+        let y = fragment.vertex_position.y;
+        let noise_value = uniforms.noise_open_simplex.get_noise_2d(x * self.zoom, y * self.zoom);
+        let spot_color = Color::new(255, 255, 255);
+        let base_color = Color::new(0, 0, 0);
+
+        ((if noise_value < self.spot_threshold { spot_color } else { base_color }) * lit_intensity(fragment, uniforms)).into()
+    }
+}
 
-    (if noise_value > cloud_threshold { cloud_color } else { sky_color }) * fragment.intensity
+/// Nubes blancas que se desplazan sobre un cielo azul.
+pub struct Cloud {
+    pub zoom: f32,
+    pub speed: f32,
+    pub cloud_threshold: f32,
 }
 
-pub fn cellular_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let zoom = 30.0;
-    let x = fragment.vertex_position.x;
-    let y = fragment.vertex_position.y;
-    let cell_noise_value = uniforms.noise_cellular.get_noise_2d(x * zoom, y * zoom).abs();
-    let cell_color_1 = Color::new(85, 107, 47);
-    let cell_color_2 = Color::new(124, 252, 0);
-    let cell_color_3 = Color::new(34, 139, 34);
-    let cell_color_4 = Color::new(173, 255, 47);
-
-    (if cell_noise_value < 0.15 {
-        cell_color_1
-    } else if cell_noise_value < 0.7 {
-        cell_color_2
-    } else if cell_noise_value < 0.75 {
-        cell_color_3
-    } else {
-        cell_color_4
-    }) * fragment.intensity
+impl Default for Cloud {
+    fn default() -> Self {
+        Cloud { zoom: 100.0, speed: 0.5, cloud_threshold: 0.5 }
+    }
 }
 
-pub fn get_experimental_shader(fragment: &Fragment, uniforms: &Uniforms, shader_type: &str) -> Color {
-    match shader_type {
-        "random_color" => random_color_shader(fragment, uniforms),
-        "panda" => panda_shader(fragment, uniforms),
-        "cloud" => cloud_shader(fragment, uniforms),
-        "cellular" => cellular_shader(fragment, uniforms),
-        _ => Color::new(0, 0, 0),
+impl FragmentShader for Cloud {
+    fn shade(&self, fragment: &Fragment, uniforms: &Uniforms) -> HdrColor {
+        let x = fragment.vertex_position.x;
+        let y = fragment.vertex_position.y;
+        let t = uniforms.time as f32 * self.speed;
+        let noise_value = uniforms.noise_open_simplex.get_noise_2d(x * self.zoom + t, y * self.zoom);
+        let cloud_color = Color::new(255, 255, 255);
+        let sky_color = Color::new(30, 97, 145);
+
+        ((if noise_value > self.cloud_threshold { cloud_color } else { sky_color }) * lit_intensity(fragment, uniforms)).into()
     }
 }
+
+/// Celdas verdes a partir del ruido celular.
+pub struct Cellular {
+    pub zoom: f32,
+}
+
+impl Default for Cellular {
+    fn default() -> Self {
+        Cellular { zoom: 30.0 }
+    }
+}
+
+impl FragmentShader for Cellular {
+    fn shade(&self, fragment: &Fragment, uniforms: &Uniforms) -> HdrColor {
+        let x = fragment.vertex_position.x;
+        let y = fragment.vertex_position.y;
+        let cell_noise_value = uniforms.noise_cellular.get_noise_2d(x * self.zoom, y * self.zoom).abs();
+        let cell_color_1 = Color::new(85, 107, 47);
+        let cell_color_2 = Color::new(124, 252, 0);
+        let cell_color_3 = Color::new(34, 139, 34);
+        let cell_color_4 = Color::new(173, 255, 47);
+
+        ((if cell_noise_value < 0.15 {
+            cell_color_1
+        } else if cell_noise_value < 0.7 {
+            cell_color_2
+        } else if cell_noise_value < 0.75 {
+            cell_color_3
+        } else {
+            cell_color_4
+        }) * lit_intensity(fragment, uniforms)).into()
+    }
+}
+
+/// Registra los shaders experimentales; devuelven el color final sin pasar por la iluminación común.
+pub fn register_shaders(registry: &mut ShaderRegistry) {
+    registry.register("static_pattern", Unlit(|fragment, _| static_pattern_shader(fragment)));
+    registry.register("moving_circles", Unlit(moving_circles_shader));
+    registry.register("combined", Unlit(combined_shader));
+    registry.register("combined_blend", CombinedBlend::default());
+    registry.register("neon", Unlit(|fragment, _| neon_light_shader(fragment)));
+    registry.register("random_color", Unlit(random_color_shader));
+    registry.register("panda", Panda::default());
+    registry.register("cloud", Cloud::default());
+    registry.register("cellular", Cellular::default());
+}
@@ -0,0 +1,162 @@
+use nalgebra_glm::{Mat4, Vec2, Vec3, Vec4};
+use crate::color::HdrColor;
+use crate::framebuffer::Framebuffer;
+use crate::math::smoothstep;
+
+/// Ajustes del destello de lente que produce el Sol cuando se ve en pantalla.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LensFlareSettings {
+    // Brillo del destello; 0 lo desactiva
+    pub intensity: f32,
+}
+
+impl Default for LensFlareSettings {
+    fn default() -> Self {
+        LensFlareSettings { intensity: 1.0 }
+    }
+}
+
+/// El Sol proyectado en pantalla y la fracción de su disco que no queda tapada.
+pub struct SunOnScreen {
+    pub position: Vec2,
+    pub visibility: f32,
+}
+
+// Puntos de prueba por eje sobre el disco del Sol
+const VISIBILITY_SAMPLES: i32 = 9;
+
+// Reflejos sobre la recta que une el Sol con el centro de la pantalla:
+// posición (0 = Sol, 1 = centro, 2 = reflejado al otro lado), radio relativo a la pantalla y color
+const GHOSTS: [(f32, f32, HdrColor); 6] = [
+    (0.45, 0.030, HdrColor::new(0.10, 0.18, 0.30)),
+    (0.75, 0.055, HdrColor::new(0.18, 0.12, 0.05)),
+    (1.15, 0.018, HdrColor::new(0.20, 0.20, 0.10)),
+    (1.40, 0.080, HdrColor::new(0.04, 0.10, 0.08)),
+    (1.70, 0.040, HdrColor::new(0.15, 0.06, 0.12)),
+    (2.10, 0.120, HdrColor::new(0.03, 0.05, 0.09)),
+];
+
+// Radio del destello en estrella relativo a la pantalla, cantidad de rayos y color
+const STARBURST_RADIUS: f32 = 0.3;
+const STARBURST_RAYS: f32 = 12.0;
+const STARBURST_COLOR: HdrColor = HdrColor::new(0.9, 0.75, 0.5);
+
+/// Proyecta el Sol, una esfera de `radius` en `center`, con `transform` (viewport * proyección * vista)
+/// y prueba puntos de su disco contra el z-buffer. `None` si no se ve nada del Sol.
+pub fn sun_on_screen(framebuffer: &Framebuffer, center: Vec3, radius: f32, camera_direction: Vec3, transform: &Mat4) -> Option<SunOnScreen> {
+    let project = |p: Vec3| {
+        let clip = transform * Vec4::new(p.x, p.y, p.z, 1.0);
+        clip.xyz() / clip.w
+    };
+
+    // Cualquier eje perpendicular a la vista da el radio del disco en pantalla
+    let up = if camera_direction.z.abs() < 0.9 { Vec3::z() } else { Vec3::x() };
+    let side = camera_direction.cross(&up).normalize();
+
+    let screen_center = project(center);
+    let front = project(center - camera_direction * radius);
+    let screen_radius = (project(center + side * radius).xy() - screen_center.xy()).magnitude();
+    let bulge = screen_center.z - front.z;
+    // Las caras de la malla se apartan algo de la esfera ideal
+    let tolerance = bulge * 0.1 + 1.0;
+
+    let half = VISIBILITY_SAMPLES / 2;
+    let mut visible = 0;
+    let mut total = 0;
+    for j in -half..=half {
+        for i in -half..=half {
+            // Solo la parte central del disco, lejos del borde facetado
+            let offset = Vec2::new(i as f32, j as f32) / half as f32 * 0.8;
+            let t2 = offset.magnitude_squared();
+            if t2 > 0.64 {
+                continue;
+            }
+            total += 1;
+
+            let pixel = screen_center.xy() + offset * screen_radius;
+            if pixel.x < 0.0 || pixel.y < 0.0 || pixel.x >= framebuffer.width as f32 || pixel.y >= framebuffer.height as f32 {
+                continue;
+            }
+
+            // Profundidad de la superficie del Sol en ese punto del disco
+            let expected = screen_center.z - bulge * (1.0 - t2).sqrt();
+            if framebuffer.depth(pixel.x as usize, pixel.y as usize) >= expected - tolerance {
+                visible += 1;
+            }
+        }
+    }
+
+    if visible == 0 {
+        return None;
+    }
+
+    Some(SunOnScreen {
+        position: screen_center.xy(),
+        visibility: visible as f32 / total as f32,
+    })
+}
+
+/// Suma el destello de lente del Sol sobre una imagen HDR lineal de `width` x `height`.
+pub fn apply(image: &mut [HdrColor], width: usize, height: usize, sun: &SunOnScreen, settings: &LensFlareSettings) {
+    if settings.intensity <= 0.0 || width == 0 || height == 0 {
+        return;
+    }
+
+    let screen_center = Vec2::new(width as f32 / 2.0, height as f32 / 2.0);
+    let axis = screen_center - sun.position;
+
+    // Se apaga a medida que el Sol se acerca al borde de la pantalla y sale de ella
+    let edge_distance = (axis.x.abs() / screen_center.x).max(axis.y.abs() / screen_center.y);
+    let edge_fade = 1.0 - smoothstep(0.6, 1.1, edge_distance);
+    let strength = settings.intensity * sun.visibility * edge_fade;
+    if strength <= 0.0 {
+        return;
+    }
+
+    let size = width.min(height) as f32;
+
+    // Estrella: rayos finos alrededor del Sol más un núcleo suave
+    add_sprite(image, width, height, sun.position, size * STARBURST_RADIUS, |offset| {
+        let distance = offset.magnitude();
+        let angle = offset.y.atan2(offset.x);
+        let rays = (angle * STARBURST_RAYS / 2.0).cos().abs().powi(24);
+        let falloff = (1.0 - distance).max(0.0);
+        let core = (-distance * 10.0).exp();
+        STARBURST_COLOR * ((rays * falloff.powi(3) * 0.6 + core * 0.5) * strength)
+    });
+
+    // Reflejos internos de la lente: discos suaves con el borde algo más brillante
+    for (position, radius, color) in GHOSTS {
+        let ghost_center = sun.position + axis * position;
+        add_sprite(image, width, height, ghost_center, size * radius, |offset| {
+            let distance = offset.magnitude();
+            let disc = 1.0 - smoothstep(0.75, 1.0, distance);
+            color * (disc * (0.4 + 0.6 * distance * distance) * strength)
+        });
+    }
+}
+
+// Suma `shade` sobre el cuadrado que rodea al círculo de `radius` en `center`; `shade` recibe el desplazamiento
+// normalizado por el radio
+fn add_sprite(image: &mut [HdrColor], width: usize, height: usize, center: Vec2, radius: f32, shade: impl Fn(Vec2) -> HdrColor) {
+    if radius <= 0.0 {
+        return;
+    }
+
+    let min_x = (center.x - radius).floor().max(0.0) as usize;
+    let min_y = (center.y - radius).floor().max(0.0) as usize;
+    let max_x = ((center.x + radius).ceil().max(0.0) as usize).min(width);
+    let max_y = ((center.y + radius).ceil().max(0.0) as usize).min(height);
+
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let offset = (Vec2::new(x as f32 + 0.5, y as f32 + 0.5) - center) / radius;
+            if offset.magnitude_squared() >= 1.0 {
+                continue;
+            }
+            let index = y * width + x;
+            image[index] = image[index] + shade(offset);
+        }
+    }
+}
+
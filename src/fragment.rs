@@ -1,202 +1,350 @@
 use nalgebra_glm::{Vec2, Vec3};
-use crate::color::Color;
+use crate::color::{Color, Gradient, HdrColor};
+use crate::material::{bump_normal, Material};
+use crate::shader_registry::{FragmentShader, ShaderRegistry, SurfaceShader};
 use crate::Uniforms;
-use fastnoise_lite::FastNoiseLite;
+use crate::noise::Noise;
 
 pub struct Fragment {
     pub position: Vec3,
     pub color: Color,
     pub depth: f32,
     pub normal: Vec3,
-    pub intensity: f32,
-    pub vertex_position: Vec3
+    pub vertex_position: Vec3,
+    pub tex_coords: Vec2,
+    // Posición y normal interpoladas en espacio de mundo
+    pub world_position: Vec3,
+    pub world_normal: Vec3,
+    // Dirección unitaria del fragmento hacia la cámara, en espacio de mundo
+    pub view_dir: Vec3,
+    // Fracción de la luz del Sol que llega al fragmento (1 = sin sombra)
+    pub visibility: f32,
+    // Máscara de muestras MSAA cubiertas (bit i = muestra i)
+    pub coverage: u32,
+    // Variación de la profundidad por píxel en x e y, para evaluarla en cada muestra
+    pub depth_slope: (f32, f32),
+    // Variación de las coordenadas de textura por píxel en x e y, para elegir el nivel de mipmap
+    pub tex_coords_slope: (Vec2, Vec2),
 }
 
 impl Fragment {
-    pub fn new(position: Vec3, color: Color, depth: f32, normal: Vec3, intensity: f32, vertex_position: Vec3) -> Self {
+    pub fn new(position: Vec3, color: Color, depth: f32, normal: Vec3, vertex_position: Vec3) -> Self {
         Fragment {
             position,
             color,
             depth,
             normal,
-            intensity,
-            vertex_position
+            vertex_position,
+            tex_coords: Vec2::new(0.0, 0.0),
+            world_position: vertex_position,
+            world_normal: normal,
+            view_dir: Vec3::new(0.0, 0.0, -1.0),
+            visibility: 1.0,
+            coverage: 1,
+            depth_slope: (0.0, 0.0),
+            tex_coords_slope: (Vec2::new(0.0, 0.0), Vec2::new(0.0, 0.0)),
         }
     }
 }
 
 // Shaders para planetas
-fn solar_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let x = fragment.vertex_position.x;
-    let y = fragment.vertex_position.y;
 
-    let slowed_time = uniforms.time as f32 / 3.0;
-    let time_factor = (slowed_time * 0.05).sin() * 0.4 + 0.8;
+/// Superficie del Sol: ruido animado que pulsa, con halo hacia el borde.
+pub struct SolarSurface {
+    pub zoom: f32,
+    pub pulse_speed: f32,
+}
+
+impl Default for SolarSurface {
+    fn default() -> Self {
+        SolarSurface {
+            zoom: 15.0,
+            pulse_speed: 0.05,
+        }
+    }
+}
 
-    let zoom = 15.0;
-    let noise_value = uniforms.noise_open_simplex.get_noise_2d(x * zoom, y * zoom) * 0.3 + 0.7;
-    let surface_intensity = (0.9 + noise_value * 0.1) * time_factor;
+impl SurfaceShader for SolarSurface {
+    fn surface(&self, fragment: &Fragment, uniforms: &Uniforms) -> Material {
+        let x = fragment.vertex_position.x;
+        let y = fragment.vertex_position.y;
 
-    let r = (255.0 * surface_intensity) as u8;
-    let g = (200.0 * surface_intensity) as u8;
-    let b = (50.0 * surface_intensity) as u8;
+        let slowed_time = uniforms.time as f32 / 3.0;
+        let time_factor = (slowed_time * self.pulse_speed).sin() * 0.4 + 0.8;
 
-    let core_color = Color::new(r, g, b) * fragment.intensity;
+        let zoom = self.zoom;
+        let noise_value = uniforms.noise_open_simplex.get_noise_2d(x * zoom, y * zoom) * 0.3 + 0.7;
+        let surface_intensity = (0.9 + noise_value * 0.1) * time_factor;
 
-    // Efecto de halo alrededor del Sol
-    let distance_to_center = (x.powi(2) + y.powi(2)).sqrt();
-    let halo_threshold = 0.0;
-    let halo_intensity = if distance_to_center > halo_threshold {
-        ((distance_to_center - halo_threshold) * 3.0).exp().min(1.0)
-    } else {
-        0.0
-    };
+        // Los términos se calculan en `HdrColor` para que pasen de 1.0 sin saturar
+        let core_color = HdrColor::from(Color::new(255, 200, 50)) * surface_intensity;
 
-    let halo_color = Color::new(255, 140, 0) * halo_intensity;
+        // Iluminación ambiental para que toda la esfera tenga visibilidad mínima
+        let ambient_intensity = 0.1;
+        let ambient_color = HdrColor::from(Color::new(255, 100, 50)) * ambient_intensity;
 
-    // Iluminación ambiental para que toda la esfera tenga visibilidad mínima
-    let ambient_intensity = 0.1;
-    let ambient_color = Color::new(255, 100, 50) * ambient_intensity;
+        // El Sol emite su propia luz: superficie y luz ambiental; la suma puede superar 1.0 y se deja
+        // sin recortar para que el tone mapping decida cómo comprimirla. El resplandor lo agrega el bloom
+        let emissive = core_color + ambient_color;
+        Material::new(Color::black()).with_emissive(emissive)
+    }
+}
 
-    // Mezcla del color de la superficie, halo, y luz ambiental
-    core_color.blend_add(&halo_color).blend_add(&ambient_color)
+/// Planeta rocoso con vetas diagonales.
+pub struct RockyPlanet {
+    pub zoom: f32,
+    pub ambient_intensity: f32,
+    pub ambient_color: Color,
 }
 
-fn rocky_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let zoom = 8.0;
-    let x = fragment.vertex_position.x;
-    let y = fragment.vertex_position.y;
-    
-    // Ajuste para el patrón de color en la superficie
-    let color_variation = (x * zoom + y * zoom).sin().abs();
-    let main_color = Color::new(139, (69.0 * color_variation) as u8, (19.0 * color_variation) as u8);
+impl Default for RockyPlanet {
+    fn default() -> Self {
+        RockyPlanet {
+            zoom: 8.0,
+            ambient_intensity: 0.7,
+            ambient_color: Color::new(60, 30, 10),
+        }
+    }
+}
 
-    // Ajuste de iluminación ambiental para todo el planeta
-    let ambient_intensity = 0.7;
-    let ambient_color = Color::new(60, 30, 10);
+impl SurfaceShader for RockyPlanet {
+    fn surface(&self, fragment: &Fragment, _uniforms: &Uniforms) -> Material {
+        let zoom = self.zoom;
+        let x = fragment.vertex_position.x;
+        let y = fragment.vertex_position.y;
 
-    // Mezcla el color principal con la luz ambiental, eliminando sombras fuertes
-    main_color * fragment.intensity + ambient_color * ambient_intensity
+        // Ajuste para el patrón de color en la superficie
+        let color_variation = (x * zoom + y * zoom).sin().abs();
+        let main_color = Color::new(139, (69.0 * color_variation) as u8, (19.0 * color_variation) as u8);
+
+        // Ajuste de iluminación ambiental para todo el planeta
+        let ambient_intensity = self.ambient_intensity;
+        let ambient_color = self.ambient_color;
+
+        // La luz ambiental alta suaviza las sombras fuertes
+        Material::new(main_color).with_ambient(ambient_color * ambient_intensity)
+    }
+}
+
+/// Gigante gaseoso azulado con ondas que se desplazan.
+pub struct GasGiant {
+    pub zoom: f32,
+    pub speed: f32,
+    pub ambient_intensity: f32,
+    pub ambient_color: Color,
+}
+
+impl Default for GasGiant {
+    fn default() -> Self {
+        GasGiant {
+            zoom: 3.0,
+            speed: 0.008,
+            ambient_intensity: 0.5,
+            ambient_color: Color::new(80, 130, 200),
+        }
+    }
 }
 
-fn gas_giant_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let zoom = 3.0;
-    let x = fragment.vertex_position.x;
-    let y = fragment.vertex_position.y;
-    let time = uniforms.time as f32 * 0.008;
+impl SurfaceShader for GasGiant {
+    fn surface(&self, fragment: &Fragment, uniforms: &Uniforms) -> Material {
+        let zoom = self.zoom;
+        let x = fragment.vertex_position.x;
+        let y = fragment.vertex_position.y;
+        let time = uniforms.time as f32 * self.speed;
 
-    // Crear un patrón basado en ondas para un efecto gaseoso dinámico
-    let pattern1 = ((x * zoom + time).sin() * (y * zoom + time).cos()).abs();
-    let pattern2 = ((x * zoom * 0.5 - time).cos() * (y * zoom * 0.7 + time).sin()).abs();
-    let combined_pattern = (pattern1 + pattern2 * 0.5).min(1.0);
+        // Crear un patrón basado en ondas para un efecto gaseoso dinámico
+        let pattern1 = ((x * zoom + time).sin() * (y * zoom + time).cos()).abs();
+        let pattern2 = ((x * zoom * 0.5 - time).cos() * (y * zoom * 0.7 + time).sin()).abs();
+        let combined_pattern = (pattern1 + pattern2 * 0.5).min(1.0);
 
-    // Colores de base con tonos más azulados y menos fucsia
-    let r = (combined_pattern * 100.0) as u8;
-    let g = ((1.0 - combined_pattern) * 170.0) as u8;
-    let b = 240;
+        // Colores de base con tonos más azulados y menos fucsia
+        let r = (combined_pattern * 100.0) as u8;
+        let g = ((1.0 - combined_pattern) * 170.0) as u8;
+        let b = 240;
 
-    let base_color = Color::new(r, g, b);
+        let base_color = Color::new(r, g, b);
 
-    // Ajuste de iluminación ambiental para un aspecto de gas disperso
-    let ambient_intensity = 0.5;
-    let ambient_color = Color::new(80, 130, 200);
+        // Ajuste de iluminación ambiental para un aspecto de gas disperso
+        let ambient_intensity = self.ambient_intensity;
+        let ambient_color = self.ambient_color;
 
-    // Mezcla del color base y el color ambiental para dar una apariencia gaseosa en toda la superficie
-    base_color * fragment.intensity + ambient_color * ambient_intensity
+        // El color ambiental da una apariencia gaseosa en toda la superficie
+        Material::new(base_color).with_ambient(ambient_color * ambient_intensity)
+    }
+}
+
+/// Gigante gaseoso con bandas horizontales, el del planeta con anillos.
+pub struct GasGiantWithRings {
+    pub zoom: f32,
+    pub speed: f32,
+    pub ambient_intensity: f32,
+    pub ambient_color: Color,
+}
+
+impl Default for GasGiantWithRings {
+    fn default() -> Self {
+        GasGiantWithRings {
+            zoom: 4.0,
+            speed: 0.01,
+            ambient_intensity: 0.5,
+            ambient_color: Color::new(90, 60, 120),
+        }
+    }
 }
 
-fn gas_giant_with_rings_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let zoom = 4.0;
-    let x = fragment.vertex_position.x;
-    let y = fragment.vertex_position.y;
-    let time = uniforms.time as f32 * 0.01;
+impl SurfaceShader for GasGiantWithRings {
+    fn surface(&self, fragment: &Fragment, uniforms: &Uniforms) -> Material {
+        let zoom = self.zoom;
+        let x = fragment.vertex_position.x;
+        let y = fragment.vertex_position.y;
+        let time = uniforms.time as f32 * self.speed;
 
-    // Crear un patrón gaseoso con bandas horizontales
-    let pattern1 = ((x * zoom).sin() * (y * zoom * 0.5 + time).cos()).abs();
-    let pattern2 = ((x * zoom * 0.7 - time).cos() * (y * zoom * 0.3 + time).sin()).abs();
-    let combined_pattern = (pattern1 * 0.6 + pattern2 * 0.4).min(1.0);
+        // Crear un patrón gaseoso con bandas horizontales
+        let pattern1 = ((x * zoom).sin() * (y * zoom * 0.5 + time).cos()).abs();
+        let pattern2 = ((x * zoom * 0.7 - time).cos() * (y * zoom * 0.3 + time).sin()).abs();
+        let combined_pattern = (pattern1 * 0.6 + pattern2 * 0.4).min(1.0);
 
-    // Nuevos colores para distinguir este planeta
-    let r = (combined_pattern * 220.0) as u8;
-    let g = ((1.0 - combined_pattern) * 130.0 + 80.0) as u8;
-    let b = 120;
+        // Nuevos colores para distinguir este planeta
+        let r = (combined_pattern * 220.0) as u8;
+        let g = ((1.0 - combined_pattern) * 130.0 + 80.0) as u8;
+        let b = 120;
 
-    let base_color = Color::new(r, g, b);
+        let base_color = Color::new(r, g, b);
 
-    // Iluminación ambiental para un efecto gaseoso uniforme
-    let ambient_intensity = 0.5;
-    let ambient_color = Color::new(90, 60, 120);
+        // Iluminación ambiental para un efecto gaseoso uniforme
+        let ambient_intensity = self.ambient_intensity;
+        let ambient_color = self.ambient_color;
 
-    // Mezcla del color base y el color ambiental
-    base_color * fragment.intensity + ambient_color * ambient_intensity
+        Material::new(base_color).with_ambient(ambient_color * ambient_intensity)
+    }
 }
 
-pub fn ring_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let color1 = Color::new(220, 200, 180);  
-    let color2 = Color::new(150, 100, 70);   
-    let color3 = Color::new(50, 30, 20);     
+/// Anillos translúcidos con bandas y ruido.
+pub struct Ring {
+    pub zoom: f32,
+    pub noise_zoom: f32,
+    pub ambient_intensity: f32,
+    // De las bandas claras (0) a las oscuras (1)
+    pub palette: Gradient,
+}
 
-    let position = Vec3::new(
-        fragment.vertex_position.x,
-        fragment.vertex_position.y,
-        fragment.depth,
-    );
+impl Default for Ring {
+    fn default() -> Self {
+        Ring {
+            zoom: 10.0,
+            noise_zoom: 10.0,
+            ambient_intensity: 0.4,
+            // Muestras cada 0.25 de la mezcla doble que usaba el shader: de (220, 200, 180) hacia
+            // (150, 100, 70) y de ahí hacia (50, 30, 20), las dos con el mismo valor
+            palette: Gradient::new(vec![
+                (0.0, Color::new(220, 200, 180)),
+                (0.25, Color::new(164, 139, 119)),
+                (0.5, Color::new(118, 90, 73)),
+                (0.75, Color::new(79, 54, 39)),
+                (1.0, Color::new(50, 30, 20)),
+            ]),
+        }
+    }
+}
+
+impl SurfaceShader for Ring {
+    fn surface(&self, fragment: &Fragment, uniforms: &Uniforms) -> Material {
+        let position = Vec3::new(
+            fragment.vertex_position.x,
+            fragment.vertex_position.y,
+            fragment.depth,
+        );
+
+        let zoom = self.zoom;
+        let noise_zoom = self.noise_zoom;
+
+        let line_pattern = (position.y * zoom).sin().abs();
 
-    let zoom = 10.0;
-    let noise_zoom = 10.0;
+        let ruido = uniforms.noise_open_simplex.get_noise_3d(
+            position.x * noise_zoom,
+            position.y * noise_zoom,
+            position.z * noise_zoom,
+        );
 
-    let line_pattern = (position.y * zoom).sin().abs();
+        let val_normalizado = (line_pattern * 0.7 + ruido * 0.3).clamp(0.0, 1.0);
 
-    let ruido = uniforms.noise_open_simplex.get_noise_3d(
-        position.x * noise_zoom,
-        position.y * noise_zoom,
-        position.z * noise_zoom,
-    );
+        let final_color = self.palette.sample(val_normalizado);
 
-    let val_normalizado = (line_pattern * 0.7 + ruido * 0.3).clamp(0.0, 1.0);
+        // Las bandas oscuras son más transparentes que las claras
+        let alpha = (0.85 - val_normalizado * 0.55).clamp(0.0, 1.0);
 
-    let color_intermediate = color1.lerp(&color2, val_normalizado);
-    let final_color = color_intermediate.lerp(&color3, val_normalizado);
+        // La parte ambiental evita que la sombra del planeta apague los anillos del todo
+        let albedo = final_color * 0.9;
+        Material::new(albedo.with_alpha((alpha * 255.0) as u8)).with_ambient(albedo * self.ambient_intensity)
+    }
+}
+
+/// Planeta de ondas concéntricas multicolores.
+pub struct Colorful {
+    pub frequency: f32,
+    pub speed: f32,
+    pub ambient_intensity: f32,
+    pub ambient_color: Color,
+    // Colores de las ondas; ver `chained_mix`
+    pub palette: Gradient,
+}
 
-    final_color * 0.9
+impl Default for Colorful {
+    fn default() -> Self {
+        Colorful {
+            frequency: 6.0,
+            speed: 0.006,
+            ambient_intensity: 0.5,
+            ambient_color: Color::new(90, 60, 120),
+            palette: Gradient::evenly_spaced(&[
+                Color::new(255, 223, 75), // amarillo
+                Color::new(255, 165, 0), // naranja
+                Color::new(238, 130, 238), // lila
+                Color::new(173, 216, 230), // celeste
+                Color::new(255, 105, 180), // rosa
+            ]),
+        }
+    }
 }
 
-pub fn planet_colorful(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let color1 = Color::new(255, 223, 75); 
-    let color2 = Color::new(255, 165, 0);  
-    let color3 = Color::new(238, 130, 238);
-    let color4 = Color::new(173, 216, 230); 
-    let color5 = Color::new(255, 105, 180);
+impl SurfaceShader for Colorful {
+    fn surface(&self, fragment: &Fragment, uniforms: &Uniforms) -> Material {
+        let x = fragment.vertex_position.x;
+        let y = fragment.vertex_position.y;
+        let tiempo = (uniforms.time as f32) * self.speed;
 
-    let x = fragment.vertex_position.x;
-    let y = fragment.vertex_position.y;
-    let tiempo = (uniforms.time as f32) * 0.006;
+        // Frecuencia y ruido para patrones
+        let frecuencia = self.frequency;
+        let distancia = (x * x + y * y).sqrt();
 
-    // Frecuencia y ruido para patrones
-    let frecuencia = 6.0;
-    let distancia = (x * x + y * y).sqrt();
+        // Generación de ruido suave en la superficie
+        let ruido = (x * 0.3 + tiempo).sin() * (y * 0.3).cos() * 0.5;
 
-    // Generación de ruido suave en la superficie
-    let ruido = (x * 0.3 + tiempo).sin() * (y * 0.3).cos() * 0.5;
+        // Patrones de ondas en varias direcciones
+        let patron1 = ((distancia + ruido) * frecuencia + (y + ruido) * 3.0).sin() * 0.5 + 0.5;
+        let patron2 = ((distancia + ruido) * frecuencia * 0.8 - (x + ruido) * 3.0).sin() * 0.5 + 0.5;
+        let patron3 = ((distancia + ruido) * frecuencia * 1.2 + (x + ruido) * 4.0).sin() * 0.5 + 0.5;
 
-    // Patrones de ondas en varias direcciones
-    let patron1 = ((distancia + ruido) * frecuencia + (y + ruido) * 3.0).sin() * 0.5 + 0.5;
-    let patron2 = ((distancia + ruido) * frecuencia * 0.8 - (x + ruido) * 3.0).sin() * 0.5 + 0.5;
-    let patron3 = ((distancia + ruido) * frecuencia * 1.2 + (x + ruido) * 4.0).sin() * 0.5 + 0.5;
+        // Interpolación de colores según los patrones generados
+        let color_final = chained_mix(&self.palette, &[patron1, patron2, patron3, patron1 * patron2]);
 
-    // Interpolación de colores según los patrones generados
-    let mut color_final = color1.lerp(&color2, patron1);
-    color_final = color_final.lerp(&color3, patron2);
-    color_final = color_final.lerp(&color4, patron3);
-    color_final = color_final.lerp(&color5, patron1 * patron2);
+        let ambient_intensity = self.ambient_intensity;
+        let ambient_color = self.ambient_color;
 
-    let ambient_intensity = 0.5;
-    let ambient_color = Color::new(90, 60, 120);
+        Material::new(color_final).with_ambient(ambient_color * ambient_intensity)
+    }
+}
 
-    color_final * fragment.intensity + ambient_color * ambient_intensity
+// Mezcla en cadena de las paradas de `palette`, en orden: parte de la primera y se acerca a cada una de las
+// siguientes con su peso en `weights`. Con varios patrones independientes el color no depende de un único valor,
+// así que la rampa no se muestrea con `Gradient::sample`.
+fn chained_mix(palette: &Gradient, weights: &[f32]) -> Color {
+    let mut colors = palette.stops().iter().map(|stop| stop.1);
+    let first = colors.next().unwrap_or_else(Color::black);
+    colors.zip(weights).fold(first, |color, (next, &weight)| color.lerp(&next, weight))
 }
 
-fn ruido_fractal(noise: &FastNoiseLite, x: f32, y: f32, octaves: u32, lacunarity: f32, gain: f32) -> f32 {
+pub fn ruido_fractal(noise: &Noise, x: f32, y: f32, octaves: u32, lacunarity: f32, gain: f32) -> f32 {
     let mut total = 0.0;
     let mut frequency = 1.0;
     let mut amplitude = 1.0;
@@ -213,154 +361,288 @@ fn ruido_fractal(noise: &FastNoiseLite, x: f32, y: f32, octaves: u32, lacunarity
     total / max_value
 }
 
-pub fn planet_exotic_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let color_amarillo = Color::new(255, 223, 75);    
-    let color_naranja = Color::new(255, 165, 0);    
-    let color_lila = Color::new(238, 130, 238);  
-    let color_rosa = Color::new(255, 105, 180);  
-    let color_purpura = Color::new(75, 0, 130);     
+/// Planeta exótico de ruido fractal en tonos cálidos y púrpuras.
+pub struct Exotic {
+    pub zoom: f32,
+    pub speed: f32,
+    pub ambient_intensity: f32,
+    pub ambient_color: Color,
+    // Colores de las zonas del planeta; ver `chained_mix`
+    pub palette: Gradient,
+}
 
-    let position = fragment.vertex_position;
-    let t = uniforms.time as f32 * 0.6; 
+impl Default for Exotic {
+    fn default() -> Self {
+        Exotic {
+            zoom: 150.0,
+            speed: 0.6,
+            ambient_intensity: 0.3,
+            ambient_color: Color::new(30, 20, 60),
+            palette: Gradient::evenly_spaced(&[
+                Color::new(255, 223, 75), // amarillo
+                Color::new(255, 165, 0), // naranja
+                Color::new(238, 130, 238), // lila
+                Color::new(255, 105, 180), // rosa
+                Color::new(75, 0, 130), // púrpura
+            ]),
+        }
+    }
+}
 
-    let zoom = 150.0;  
-    let ruido = ruido_fractal(&uniforms.noise_open_simplex, position.x * zoom + t, position.y * zoom + t, 4, 2.0, 0.5);
+impl SurfaceShader for Exotic {
+    fn surface(&self, fragment: &Fragment, uniforms: &Uniforms) -> Material {
+        let position = fragment.vertex_position;
+        let t = uniforms.time as f32 * self.speed;
 
-    // Generar patrones de color con el ruido
-    let patron1 = (ruido * 1.5 + (position.x * 0.5).sin() * 0.5).clamp(0.0, 1.0);
-    let patron2 = ((position.y * 0.3 + ruido) * 2.0).sin().abs();
+        let zoom = self.zoom;
+        let ruido = ruido_fractal(&uniforms.noise_open_simplex, position.x * zoom + t, position.y * zoom + t, 4, 2.0, 0.5);
 
-    let ambient_intensity = 0.3;
-    let ambient_color = Color::new(30, 20, 60);
+        // Generar patrones de color con el ruido
+        let patron1 = (ruido * 1.5 + (position.x * 0.5).sin() * 0.5).clamp(0.0, 1.0);
+        let patron2 = ((position.y * 0.3 + ruido) * 2.0).sin().abs();
 
-    // Interpolación de colores entre las diferentes zonas del planeta
-    let mut color_final = color_amarillo.lerp(&color_naranja, patron1);
-    color_final = color_final.lerp(&color_lila, patron2);
-    color_final = color_final.lerp(&color_rosa, patron1 * patron2);
-    color_final = color_final.lerp(&color_purpura, (1.0 - patron1) * 0.5);
+        let ambient_intensity = self.ambient_intensity;
+        let ambient_color = self.ambient_color;
 
-    color_final * fragment.intensity + ambient_color * ambient_intensity
-}
+        // Interpolación de colores entre las diferentes zonas del planeta
+        let color_final = chained_mix(&self.palette, &[patron1, patron2, patron1 * patron2, (1.0 - patron1) * 0.5]);
 
-pub fn dark_red_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let color_negro = Color::new(10, 10, 10);       
-    let color_rojo = Color::new(150, 0, 0);         
-    let color_rojo_brillante = Color::new(255, 50, 50);
+        Material::new(color_final).with_ambient(ambient_color * ambient_intensity)
+    }
+}
 
-    let position = fragment.vertex_position;
-    let zoom = 150.0; 
-    let t = uniforms.time as f32 * 0.02; 
+/// Planeta oscuro con manchas rojas de ruido fractal.
+pub struct DarkRed {
+    pub zoom: f32,
+    pub speed: f32,
+    pub ambient_intensity: f32,
+    pub ambient_color: Color,
+    // Del fondo oscuro a las manchas más brillantes; ver `chained_mix`
+    pub palette: Gradient,
+}
 
-    let ruido = ruido_fractal(&uniforms.noise_open_simplex, position.x * zoom + t, position.y * zoom + t, 3, 2.0, 0.5);
+impl Default for DarkRed {
+    fn default() -> Self {
+        DarkRed {
+            zoom: 150.0,
+            speed: 0.02,
+            ambient_intensity: 0.7,
+            ambient_color: Color::new(100, 10, 10),
+            palette: Gradient::evenly_spaced(&[
+                Color::new(10, 10, 10), // negro
+                Color::new(150, 0, 0), // rojo
+                Color::new(255, 50, 50), // rojo brillante
+            ]),
+        }
+    }
+}
 
-    // Generar patrones de color con el ruido
-    let patron1 = (ruido * 1.5).clamp(0.0, 1.0);
-    let patron2 = ((position.y * 0.3 + ruido) * 2.0).sin().abs();
+impl SurfaceShader for DarkRed {
+    fn surface(&self, fragment: &Fragment, uniforms: &Uniforms) -> Material {
+        let position = fragment.vertex_position;
+        let zoom = self.zoom;
+        let t = uniforms.time as f32 * self.speed;
 
-    // Interpolación de colores entre las diferentes zonas del planeta
-    let mut color_final = color_negro.lerp(&color_rojo, patron1);
-    color_final = color_final.lerp(&color_rojo_brillante, patron2);
+        let ruido = ruido_fractal(&uniforms.noise_open_simplex, position.x * zoom + t, position.y * zoom + t, 3, 2.0, 0.5);
 
-    // Intensidad ambiental reducida para mantener el estilo oscuro
-    let ambient_intensity = 0.7;
-    let ambient_color = Color::new(100, 10, 10);
+        // Generar patrones de color con el ruido
+        let patron1 = (ruido * 1.5).clamp(0.0, 1.0);
+        let patron2 = ((position.y * 0.3 + ruido) * 2.0).sin().abs();
 
-    color_final * fragment.intensity + ambient_color * ambient_intensity
-}
+        // Interpolación de colores entre las diferentes zonas del planeta
+        let color_final = chained_mix(&self.palette, &[patron1, patron2]);
 
-pub fn rocky_planet_with_moon_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let color_roca = Color::new(139, 69, 19);        
-    let color_sombra = Color::new(105, 60, 45);      
-    let color_mineral = Color::new(189, 183, 107);   
+        // Intensidad ambiental reducida para mantener el estilo oscuro
+        let ambient_intensity = self.ambient_intensity;
+        let ambient_color = self.ambient_color;
 
-    // Ajuste de la frecuencia para el patrón de mosaico
-    let zoom = 300.0; 
-    let x = fragment.vertex_position.x * zoom;
-    let y = fragment.vertex_position.y * zoom;
+        Material::new(color_final).with_ambient(ambient_color * ambient_intensity)
+    }
+}
 
-    let noise_value = uniforms.noise_cellular.get_noise_2d(x, y);
-    let normalized_noise = ((noise_value + 1.0) * 0.5).clamp(0.0, 1.0);
+/// Roca fracturada con ruido celular, el planeta con luna.
+pub struct FracturedRock {
+    pub zoom: f32,
+    pub fracture_threshold: f32,
+    pub relief: f32,
+    // Color de la roca según el ruido celular, fuera de las grietas
+    pub palette: Gradient,
+}
 
-    // Definir el umbral para el efecto de fractura
-    let fracture_threshold = 0.35;
-    let is_fracture = normalized_noise > fracture_threshold;
+impl Default for FracturedRock {
+    fn default() -> Self {
+        FracturedRock {
+            zoom: 300.0,
+            fracture_threshold: 0.35,
+            relief: 0.08,
+            // Muestras cada 0.25 de la mezcla doble que usaba el shader: la roca (139, 69, 19) hacia la
+            // sombra (105, 60, 45) con 0.8 del ruido y de ahí hacia el mineral (189, 183, 107) con 0.5
+            palette: Gradient::new(vec![
+                (0.0, Color::new(139, 69, 19)),
+                (0.25, Color::new(139, 82, 35)),
+                (0.5, Color::new(141, 95, 49)),
+                (0.75, Color::new(145, 108, 62)),
+                (1.0, Color::new(150, 122, 73)),
+            ]),
+        }
+    }
+}
 
-    // Interpolación patrón rocoso y simular grietas
-    let color_intermediate = color_roca.lerp(&color_sombra, normalized_noise * 0.8);
-    let base_color = color_intermediate.lerp(&color_mineral, normalized_noise * 0.5);
+impl SurfaceShader for FracturedRock {
+    fn surface(&self, fragment: &Fragment, uniforms: &Uniforms) -> Material {
+        // Ajuste de la frecuencia para el patrón de mosaico
+        let zoom = self.zoom;
+        let x = fragment.vertex_position.x * zoom;
+        let y = fragment.vertex_position.y * zoom;
+
+        let noise_value = uniforms.noise_cellular.get_noise_2d(x, y);
+        let normalized_noise = ((noise_value + 1.0) * 0.5).clamp(0.0, 1.0);
+
+        // Definir el umbral para el efecto de fractura
+        let fracture_threshold = self.fracture_threshold;
+        let is_fracture = normalized_noise > fracture_threshold;
+
+        // Patrón rocoso y grietas
+        let base_color = self.palette.sample(normalized_noise);
+
+        let final_color = if is_fracture {
+            Color::new(60, 30, 10)
+        } else {
+            base_color
+        };
+
+        // Relieve: el mismo ruido celular hunde las grietas, así que reaccionan a la dirección de la luz
+        let relief = self.relief;
+        let normal = bump_normal(fragment, &uniforms.model_matrix, |p| {
+            let noise = uniforms.noise_cellular.get_noise_2d(p.x * zoom, p.y * zoom);
+            -((noise + 1.0) * 0.5).clamp(0.0, 1.0) * relief
+        });
+
+        // Superficie con algo de brillo, como roca húmeda
+        Material::new(final_color)
+            .with_specular(0.15)
+            .with_roughness(0.6)
+            .with_normal(normal)
+    }
+}
 
-    let final_color = if is_fracture {
-        Color::new(60, 30, 10)
-    } else {
-        base_color
-    };
+/// Luna gris de ruido fractal con relieve.
+pub struct Moon {
+    pub zoom: f32,
+    pub relief: f32,
+    // De las zonas bajas y oscuras (0) a las altas y claras (1)
+    pub palette: Gradient,
+}
 
-    final_color * fragment.intensity
+impl Default for Moon {
+    fn default() -> Self {
+        Moon {
+            zoom: 80.0,
+            relief: 0.3,
+            // Muestras cada 0.25 de la mezcla doble que usaba el shader: la base (50, 50, 50) hacia la
+            // sombra (20, 20, 20) con 0.8 del ruido y de ahí hacia el claro (150, 150, 150) con 0.5
+            palette: Gradient::new(vec![
+                (0.0, Color::new(50, 50, 50)),
+                (0.25, Color::new(57, 57, 57)),
+                (0.5, Color::new(66, 66, 66)),
+                (0.75, Color::new(76, 76, 76)),
+                (1.0, Color::new(88, 88, 88)),
+            ]),
+        }
+    }
 }
 
-fn moon_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let zoom = 80.0;
-    let x = fragment.vertex_position.x * zoom;
-    let y = fragment.vertex_position.y * zoom;
+impl SurfaceShader for Moon {
+    fn surface(&self, fragment: &Fragment, uniforms: &Uniforms) -> Material {
+        let zoom = self.zoom;
+        let x = fragment.vertex_position.x * zoom;
+        let y = fragment.vertex_position.y * zoom;
 
-    let ruido = ruido_fractal(&uniforms.noise_open_simplex, x, y, 3, 2.76, 0.12);
+        let ruido = ruido_fractal(&uniforms.noise_open_simplex, x, y, 3, 2.76, 0.12);
 
-    // Definimos colores oscuros y claros con un mayor contraste
-    let color_base = Color::new(50, 50, 50);
-    let color_sombra = Color::new(20, 20, 20); 
-    let color_claro = Color::new(150, 150, 150);
+        let factor = (ruido + 1.0) / 2.0;
+        let color_final = self.palette.sample(factor);
 
-    let factor = (ruido + 1.0) as f32 / 2.0;
-    let mut color_final = color_base.lerp(&color_sombra, factor * 0.8); 
-    color_final = color_final.lerp(&color_claro, factor * 0.5);
+        // Relieve a partir del mismo ruido fractal: las zonas claras sobresalen
+        let relief = self.relief;
+        let normal = bump_normal(fragment, &uniforms.model_matrix, |p| {
+            ruido_fractal(&uniforms.noise_open_simplex, p.x * zoom, p.y * zoom, 3, 2.76, 0.12) * relief
+        });
 
-    color_final * fragment.intensity
+        Material::new(color_final).with_normal(normal)
+    }
 }
 
-fn starship_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let zoom = 50.0;
-    let x = fragment.vertex_position.x;
-    let y = fragment.vertex_position.y;
-    let time = uniforms.time as f32 * 0.1;
+/// Casco metálico de la nave.
+pub struct Starship {
+    pub zoom: f32,
+    pub speed: f32,
+}
 
-    let metallic_pattern = uniforms.noise_open_simplex
-        .get_noise_2d(x * zoom + time, y * zoom - time)
-        .abs();
+impl Default for Starship {
+    fn default() -> Self {
+        Starship {
+            zoom: 50.0,
+            speed: 0.1,
+        }
+    }
+}
 
-    let base_color = Color::new(120, 120, 160);
-    let highlight_color = Color::new(255, 255, 255);
+impl SurfaceShader for Starship {
+    fn surface(&self, fragment: &Fragment, uniforms: &Uniforms) -> Material {
+        let zoom = self.zoom;
+        let x = fragment.vertex_position.x;
+        let y = fragment.vertex_position.y;
+        let time = uniforms.time as f32 * self.speed;
 
-    let blended_color = base_color.lerp(&highlight_color, metallic_pattern * 0.5);
+        let metallic_pattern = uniforms.noise_open_simplex
+            .get_noise_2d(x * zoom + time, y * zoom - time)
+            .abs();
 
-    let ambient_color = Color::new(50, 50, 80);
+        let base_color = Color::new(120, 120, 160);
+        let highlight_color = Color::new(255, 255, 255);
 
-    let light_direction = Vec3::new(0.0, 1.0, -1.0).normalize();
-    let normal = fragment.normal.normalize();
-    let specular_intensity = ((normal.dot(&light_direction)).max(0.0).powf(16.0) * 0.8)
-        .clamp(0.0, 1.0);
-    let specular_color = Color::new(255, 255, 255) * specular_intensity;
+        let blended_color = base_color.lerp(&highlight_color, metallic_pattern * 0.5);
 
-    let final_color = blended_color
-        .blend_add(&ambient_color) 
-        .blend_add(&specular_color) 
-        * fragment.intensity;
+        let ambient_color = Color::new(50, 50, 80);
 
-    final_color
+        // Casco metálico: brillo especular fuerte y poco rugoso
+        Material::new(blended_color)
+            .with_ambient(ambient_color)
+            .with_specular(0.8)
+            .with_roughness(0.55)
+    }
 }
 
-pub fn fragment_shader(fragment: &Fragment, uniforms: &Uniforms, shader_type: &str) -> Color {
-    match shader_type {
-        "solar_surface" => solar_shader(fragment, uniforms),
-        "rocky_planet_shader" => rocky_planet_shader(fragment, uniforms),
-        "gas_giant_shader" => gas_giant_shader(fragment, uniforms),
-        "gas_giant_with_rings" => gas_giant_with_rings_shader(fragment, uniforms),
-        "ring" => ring_shader(fragment, uniforms),
-        "colorful" => planet_colorful(fragment, uniforms),
-        "exotic" => planet_exotic_shader(fragment, uniforms),
-        "dark_red" => dark_red_planet_shader(fragment, uniforms),
-        "rocky_planet_with_moon_shader" => rocky_planet_with_moon_shader(fragment, uniforms),
-        "moon_shader" => moon_shader(fragment, uniforms),
-        "starship_shader" => starship_shader(fragment, uniforms),
-        _ => Color::new(0, 0, 0),
+/// Capa de atmósfera: calcula su propia dispersión de la luz con los parámetros de `uniforms.atmosphere`.
+pub struct AtmosphereShader;
+
+impl FragmentShader for AtmosphereShader {
+    fn shade(&self, fragment: &Fragment, uniforms: &Uniforms) -> HdrColor {
+        let Some(atmosphere) = &uniforms.atmosphere else {
+            return HdrColor::black();
+        };
+
+        // Centro y radio de la capa a partir de su matriz de modelo (escala uniforme)
+        let center = uniforms.model_matrix.column(3).xyz();
+        let outer_radius = uniforms.model_matrix.column(0).xyz().magnitude();
+
+        atmosphere.scatter(&center, outer_radius, &fragment.world_position, &fragment.view_dir, &uniforms.lights)
     }
 }
+
+/// Registra los shaders de planetas, anillos, luna, nave y atmósfera con sus parámetros por defecto.
+pub fn register_shaders(registry: &mut ShaderRegistry) {
+    registry.register("solar_surface", SolarSurface::default());
+    registry.register("rocky_planet_shader", RockyPlanet::default());
+    registry.register("gas_giant_shader", GasGiant::default());
+    registry.register("gas_giant_with_rings", GasGiantWithRings::default());
+    registry.register("ring", Ring::default());
+    registry.register("colorful", Colorful::default());
+    registry.register("exotic", Exotic::default());
+    registry.register("dark_red", DarkRed::default());
+    registry.register("rocky_planet_with_moon_shader", FracturedRock::default());
+    registry.register("moon_shader", Moon::default());
+    registry.register("starship_shader", Starship::default());
+    registry.register("atmosphere", AtmosphereShader);
+}
@@ -1,22 +1,161 @@
-use crate::fragment::{Fragment, fragment_shader};
+use crate::color::{Color, HdrColor};
+use crate::fragment::Fragment;
+use crate::shader_registry::FragmentShader;
+use crate::pipeline::RenderState;
+use crate::tonemap::ToneMapping;
 use crate::Uniforms;
 
+// Lado en píxeles de cada tile del rasterizador
+pub const TILE_SIZE: usize = 64;
+
+// Patrones de muestreo estándar (D3D), en 1/16 de píxel respecto al centro del píxel
+const SAMPLE_PATTERN_1X: [(i32, i32); 1] = [(0, 0)];
+const SAMPLE_PATTERN_2X: [(i32, i32); 2] = [(4, 4), (-4, -4)];
+const SAMPLE_PATTERN_4X: [(i32, i32); 4] = [(-2, -6), (6, -2), (-6, 2), (2, 6)];
+const SAMPLE_PATTERN_8X: [(i32, i32); 8] = [
+    (1, -3), (-1, 3), (5, 1), (-3, -5),
+    (-5, 5), (-7, -1), (3, 7), (7, -7),
+];
+
+pub fn sample_pattern(samples: usize) -> Option<&'static [(i32, i32)]> {
+    match samples {
+        1 => Some(&SAMPLE_PATTERN_1X),
+        2 => Some(&SAMPLE_PATTERN_2X),
+        4 => Some(&SAMPLE_PATTERN_4X),
+        8 => Some(&SAMPLE_PATTERN_8X),
+        _ => None,
+    }
+}
+
+/// Región rectangular del framebuffer con acceso exclusivo a sus filas de color y profundidad.
+/// Las coordenadas son globales (de pantalla); cada píxel guarda `samples` muestras seguidas.
+pub struct Tile<'a> {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    pub sample_pattern: &'static [(i32, i32)],
+    color_rows: Vec<&'a mut [HdrColor]>,
+    depth_rows: Vec<&'a mut [f32]>,
+}
+
+impl Tile<'_> {
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+
+    // Profundidad de cada muestra sobre el plano del triángulo
+    fn sample_depth(&self, sample: usize, depth: f32, depth_slope: (f32, f32)) -> f32 {
+        let (offset_x, offset_y) = self.sample_pattern[sample];
+        depth + (depth_slope.0 * offset_x as f32 + depth_slope.1 * offset_y as f32) / 16.0
+    }
+
+    /// Devuelve las muestras cubiertas por el fragmento que pasan la prueba de profundidad.
+    pub fn depth_test(&self, fragment: &Fragment) -> u32 {
+        let x = fragment.position.x as usize;
+        let y = fragment.position.y as usize;
+        let samples = self.sample_pattern.len();
+        let row = &self.depth_rows[y - self.y];
+        let base = (x - self.x) * samples;
+
+        (0..samples)
+            .filter(|&s| {
+                fragment.coverage & (1 << s) != 0
+                    && self.sample_depth(s, fragment.depth, fragment.depth_slope) < row[base + s]
+            })
+            .fold(0, |mask, s| mask | (1 << s))
+    }
+
+    /// Mezcla `color` en las muestras cubiertas que pasan la prueba de profundidad,
+    /// según el modo de mezcla y la escritura de profundidad de `state`.
+    pub fn write_fragment(&mut self, fragment: &Fragment, color: &HdrColor, state: &RenderState) {
+        let x = fragment.position.x as usize;
+        let y = fragment.position.y as usize;
+        if !self.contains(x, y) {
+            return;
+        }
+        let passed = self.depth_test(fragment);
+        let samples = self.sample_pattern.len();
+        let base = (x - self.x) * samples;
+
+        for s in (0..samples).filter(|&s| passed & (1 << s) != 0) {
+            if state.color_write {
+                let sample = &mut self.color_rows[y - self.y][base + s];
+                *sample = state.blend.apply(color, sample);
+            }
+            if state.depth_write {
+                self.depth_rows[y - self.y][base + s] = self.sample_depth(s, fragment.depth, fragment.depth_slope);
+            }
+        }
+    }
+}
+
+// Reparte las filas de un buffer entre los tiles, en orden de fila y luego columna
+fn split_into_tiles<T>(buffer: &mut [T], row_len: usize, segment_len: usize, tiles_x: usize, tiles_y: usize) -> Vec<Vec<&mut [T]>> {
+    let mut tiles: Vec<Vec<&mut [T]>> = (0..tiles_x * tiles_y).map(|_| Vec::with_capacity(TILE_SIZE)).collect();
+
+    for (row_index, row) in buffer.chunks_mut(row_len).enumerate() {
+        let tile_y = row_index / TILE_SIZE;
+        for (tile_x, segment) in row.chunks_mut(segment_len).enumerate() {
+            tiles[tile_y * tiles_x + tile_x].push(segment);
+        }
+    }
+
+    tiles
+}
+
 pub struct Framebuffer {
     pub width: usize,
     pub height: usize,
+    pub samples: usize,
+    // Color final por píxel tras `resolve` y `tone_map`, listo para mostrar o guardar
     pub buffer: Vec<u32>,
+    // Profundidad por muestra: `samples` valores seguidos por píxel
     pub zbuffer: Vec<f32>,
+    // Radiancia lineal por píxel, resuelta a partir de las muestras
+    pub hdr_buffer: Vec<HdrColor>,
+    // Radiancia lineal por muestra, donde escribe el rasterizador
+    sample_buffer: Vec<HdrColor>,
+    sample_pattern: &'static [(i32, i32)],
     background_color: u32,
     current_color: u32,
 }
 
 impl Framebuffer {
     pub fn new(width: usize, height: usize) -> Self {
-        Framebuffer {
+        Framebuffer::with_samples(width, height, 1).expect("1 sample is always supported")
+    }
+
+    /// Crea un framebuffer con MSAA de 1, 2, 4 u 8 muestras por píxel.
+    pub fn with_samples(width: usize, height: usize, samples: usize) -> Result<Self, String> {
+        let sample_pattern = sample_pattern(samples)
+            .ok_or_else(|| format!("Unsupported MSAA sample count {} (expected 1, 2, 4 or 8)", samples))?;
+        Ok(Framebuffer {
             width,
             height,
+            samples,
             buffer: vec![0; width * height],
+            zbuffer: vec![f32::INFINITY; width * height * samples],
+            hdr_buffer: vec![HdrColor::black(); width * height],
+            sample_buffer: vec![HdrColor::black(); width * height * samples],
+            sample_pattern,
+            background_color: 0x000000,
+            current_color: 0xFFFFFF,
+        })
+    }
+
+    /// Framebuffer sin color, solo con profundidad (p. ej. para mapas de sombra).
+    /// Solo admite dibujos con `RenderState::depth_only`.
+    pub fn depth_only(width: usize, height: usize) -> Self {
+        Framebuffer {
+            width,
+            height,
+            samples: 1,
+            buffer: Vec::new(),
             zbuffer: vec![f32::INFINITY; width * height],
+            hdr_buffer: Vec::new(),
+            sample_buffer: Vec::new(),
+            sample_pattern: &SAMPLE_PATTERN_1X,
             background_color: 0x000000,
             current_color: 0xFFFFFF,
         }
@@ -26,18 +165,50 @@ impl Framebuffer {
         for pixel in self.buffer.iter_mut() {
             *pixel = self.background_color;
         }
+        let background = HdrColor::from(Color::from_hex(self.background_color));
+        for sample in self.sample_buffer.iter_mut() {
+            *sample = background;
+        }
         for depth in self.zbuffer.iter_mut() {
             *depth = f32::INFINITY;
         }
     }
 
+    // Escribe el color actual en todas las muestras del píxel
     pub fn point(&mut self, x: usize, y: usize, depth: f32) {
         if x < self.width && y < self.height {
-            let index = y * self.width + x;
-            if self.zbuffer[index] > depth {
-                self.buffer[index] = self.current_color;
-                self.zbuffer[index] = depth;
-            }            
+            let index = (y * self.width + x) * self.samples;
+            let current_color = HdrColor::from(Color::from_hex(self.current_color));
+            let colors = &mut self.sample_buffer[index..index + self.samples];
+            let depths = &mut self.zbuffer[index..index + self.samples];
+            for (color, sample_depth) in colors.iter_mut().zip(depths.iter_mut()) {
+                if *sample_depth > depth {
+                    *color = current_color;
+                    *sample_depth = depth;
+                }
+            }
+        }
+    }
+
+    /// Profundidad más cercana entre las muestras del píxel.
+    pub fn depth(&self, x: usize, y: usize) -> f32 {
+        let index = (y * self.width + x) * self.samples;
+        self.zbuffer[index..index + self.samples].iter().cloned().fold(f32::INFINITY, f32::min)
+    }
+
+    /// Promedia las muestras de cada píxel en `hdr_buffer`.
+    pub fn resolve(&mut self) {
+        let weight = 1.0 / self.samples as f32;
+        for (pixel, samples) in self.hdr_buffer.iter_mut().zip(self.sample_buffer.chunks(self.samples)) {
+            *pixel = samples.iter().fold(HdrColor::black(), |sum, &sample| sum + sample * weight);
+        }
+    }
+
+    /// Convierte `hdr_buffer` al `buffer` de 8 bits con el operador de tone mapping indicado.
+    /// Debe llamarse después de `resolve` y antes de mostrar o guardar el cuadro.
+    pub fn tone_map(&mut self, tone_mapping: ToneMapping, exposure: f32) {
+        for (pixel, hdr) in self.buffer.iter_mut().zip(self.hdr_buffer.iter()) {
+            *pixel = tone_mapping.apply(*hdr, exposure).to_color().to_hex();
         }
     }
 
@@ -51,7 +222,38 @@ impl Framebuffer {
         self.current_color = color;
     }
 
-    pub fn process_fragments(&mut self, fragments: &[Fragment], uniforms: &Uniforms) {
+    pub fn tile_counts(&self) -> (usize, usize) {
+        (self.width.div_ceil(TILE_SIZE), self.height.div_ceil(TILE_SIZE))
+    }
+
+    /// Divide el framebuffer en tiles disjuntos que se pueden escribir en paralelo.
+    pub fn tiles_mut(&mut self) -> Vec<Tile<'_>> {
+        let (tiles_x, tiles_y) = self.tile_counts();
+        let (width, height, samples) = (self.width, self.height, self.samples);
+        let sample_pattern = self.sample_pattern;
+        let color_tiles = split_into_tiles(&mut self.sample_buffer, width * samples, TILE_SIZE * samples, tiles_x, tiles_y);
+        let depth_tiles = split_into_tiles(&mut self.zbuffer, width * samples, TILE_SIZE * samples, tiles_x, tiles_y);
+
+        color_tiles.into_iter()
+            .zip(depth_tiles)
+            .enumerate()
+            .map(|(index, (color_rows, depth_rows))| {
+                let x = (index % tiles_x) * TILE_SIZE;
+                let y = (index / tiles_x) * TILE_SIZE;
+                Tile {
+                    x,
+                    y,
+                    width: TILE_SIZE.min(width - x),
+                    height: TILE_SIZE.min(height - y),
+                    sample_pattern,
+                    color_rows,
+                    depth_rows,
+                }
+            })
+            .collect()
+    }
+
+    pub fn process_fragments(&mut self, fragments: &[Fragment], uniforms: &Uniforms, shader: &dyn FragmentShader) {
         for fragment in fragments {
             let x = fragment.position.x as usize;
             let y = fragment.position.y as usize;
@@ -59,8 +261,8 @@ impl Framebuffer {
                 let depth = fragment.position.z;
 
                 // Llamada al fragment shader para calcular el color ajustado
-                let shaded_color = fragment_shader(&fragment, uniforms, "static_pattern");
-                let color = shaded_color.to_hex();
+                let shaded_color = shader.shade(fragment, uniforms);
+                let color = shaded_color.to_color().to_hex();
                 
                 // Configura el color actual del framebuffer y dibuja el punto
                 self.set_current_color(color);
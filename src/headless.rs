@@ -0,0 +1,103 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use crate::camera::Camera;
+use crate::framebuffer::Framebuffer;
+use crate::options::RenderSettings;
+use crate::{render_scene, Scene};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageFormat {
+    Png,
+    Ppm,
+}
+
+impl ImageFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "png" => Some(ImageFormat::Png),
+            "ppm" => Some(ImageFormat::Ppm),
+            _ => None,
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Ppm => "ppm",
+        }
+    }
+}
+
+pub struct HeadlessConfig {
+    pub width: usize,
+    pub height: usize,
+    pub frames: u32,
+    pub start_time: u32,
+    pub output_dir: PathBuf,
+    pub format: ImageFormat,
+}
+
+impl Default for HeadlessConfig {
+    fn default() -> Self {
+        HeadlessConfig {
+            width: 800,
+            height: 600,
+            frames: 1,
+            start_time: 1,
+            output_dir: PathBuf::from("frames"),
+            format: ImageFormat::Png,
+        }
+    }
+}
+
+/// Renderiza `config.frames` cuadros sin abrir ventana y los guarda en `config.output_dir`.
+pub fn run(config: &HeadlessConfig, settings: &RenderSettings, scene: &Scene) -> io::Result<()> {
+    fs::create_dir_all(&config.output_dir)?;
+
+    let mut framebuffer = Framebuffer::with_samples(config.width, config.height, settings.msaa_samples)
+        .map_err(io::Error::other)?;
+    let camera = Camera::new();
+
+    for frame in 0..config.frames {
+        let time = config.start_time + frame;
+        render_scene(&mut framebuffer, scene, &camera, time, settings);
+
+        let path = config.output_dir.join(format!("frame_{:05}.{}", frame, config.format.extension()));
+        match config.format {
+            ImageFormat::Png => write_png(&path, &framebuffer)?,
+            ImageFormat::Ppm => write_ppm(&path, &framebuffer)?,
+        }
+        println!("Wrote {}", path.display());
+    }
+
+    Ok(())
+}
+
+fn rgb_bytes(framebuffer: &Framebuffer) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(framebuffer.buffer.len() * 3);
+    for pixel in &framebuffer.buffer {
+        bytes.push(((pixel >> 16) & 0xFF) as u8);
+        bytes.push(((pixel >> 8) & 0xFF) as u8);
+        bytes.push((pixel & 0xFF) as u8);
+    }
+    bytes
+}
+
+pub fn write_ppm(path: &Path, framebuffer: &Framebuffer) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write!(writer, "P6\n{} {}\n255\n", framebuffer.width, framebuffer.height)?;
+    writer.write_all(&rgb_bytes(framebuffer))?;
+    writer.flush()
+}
+
+pub fn write_png(path: &Path, framebuffer: &Framebuffer) -> io::Result<()> {
+    let writer = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(writer, framebuffer.width as u32, framebuffer.height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut png_writer = encoder.write_header().map_err(io::Error::other)?;
+    png_writer.write_image_data(&rgb_bytes(framebuffer)).map_err(io::Error::other)?;
+    png_writer.finish().map_err(io::Error::other)
+}
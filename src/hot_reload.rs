@@ -0,0 +1,95 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Vigila archivos comparando su fecha de modificación en cada consulta.
+pub struct FileWatcher {
+    // Ruta y fecha de modificación vista en la consulta anterior; `None` si no se pudo leer
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl FileWatcher {
+    pub fn new(paths: &[PathBuf]) -> Self {
+        let mut watcher = FileWatcher { files: Vec::new() };
+        watcher.watch(paths);
+        watcher
+    }
+
+    /// Cambia los archivos vigilados. Los que ya se vigilaban conservan la fecha vista, así que un
+    /// cambio que todavía no se consultó no se pierde.
+    pub fn watch(&mut self, paths: &[PathBuf]) {
+        let previous = std::mem::take(&mut self.files);
+        for path in paths {
+            if self.files.iter().any(|(watched, _)| watched == path) {
+                continue;
+            }
+            let modified = previous
+                .iter()
+                .find(|(watched, _)| watched == path)
+                .map(|(_, modified)| *modified)
+                .unwrap_or_else(|| modified_time(path));
+            self.files.push((path.clone(), modified));
+        }
+    }
+
+    /// `true` si algún archivo cambió, apareció o desapareció desde la consulta anterior.
+    pub fn poll(&mut self) -> bool {
+        let mut changed = false;
+        for (path, modified) in &mut self.files {
+            let current = modified_time(path);
+            if current != *modified {
+                *modified = current;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::time::Duration;
+
+    fn touch(path: &Path, seconds: u64) {
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds))
+            .unwrap();
+    }
+
+    #[test]
+    fn detects_changes_in_newly_watched_files() {
+        let dir = std::env::temp_dir().join("space_travel_hot_reload_test");
+        fs::create_dir_all(&dir).unwrap();
+        let config = dir.join("shaders.toml");
+        let texture = dir.join("texture.ppm");
+        fs::write(&config, "").unwrap();
+        let _ = fs::remove_file(&texture);
+
+        let mut watcher = FileWatcher::new(std::slice::from_ref(&config));
+        watcher.watch(&[config.clone(), texture.clone(), config.clone()]);
+        assert!(!watcher.poll());
+
+        // Aparece la textura y después cambia
+        fs::write(&texture, "P3\n1 1\n255\n0 0 0\n").unwrap();
+        assert!(watcher.poll());
+        touch(&texture, 1000);
+        assert!(watcher.poll());
+        assert!(!watcher.poll());
+
+        // Un cambio sin consultar sobrevive a que se vuelva a armar la lista
+        touch(&config, 2000);
+        watcher.watch(&[config.clone(), texture.clone()]);
+        assert!(watcher.poll());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
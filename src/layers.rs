@@ -0,0 +1,214 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+use nalgebra_glm::Vec3;
+use crate::color::{Color, Gradient};
+use crate::fragment::{ruido_fractal, Fragment};
+use crate::material::Material;
+use crate::shader_registry::SurfaceShader;
+use crate::texture::{Sampler, Texture};
+use crate::Uniforms;
+
+// Escala del ruido que deforma las bandas
+const BAND_WARP_ZOOM: f32 = 100.0;
+
+/// Cómo se combina una capa con lo que hay debajo, con las mezclas de `Color`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LayerBlend {
+    // Reemplaza, salvo donde la capa es negra, que deja ver lo de abajo
+    Normal,
+    Multiply,
+    Add,
+    Subtract,
+    Screen,
+}
+
+impl LayerBlend {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "normal" => Some(LayerBlend::Normal),
+            "multiply" => Some(LayerBlend::Multiply),
+            "add" => Some(LayerBlend::Add),
+            "subtract" => Some(LayerBlend::Subtract),
+            "screen" => Some(LayerBlend::Screen),
+            _ => None,
+        }
+    }
+
+    /// Mezcla `layer` sobre `base`; con `opacity` 0 queda `base` y con 1 la mezcla completa.
+    pub fn apply(&self, base: &Color, layer: &Color, opacity: f32) -> Color {
+        let blended = match self {
+            LayerBlend::Normal => base.blend_normal(layer),
+            LayerBlend::Multiply => base.blend_multiply(layer),
+            LayerBlend::Add => base.blend_add(layer),
+            LayerBlend::Subtract => base.blend_subtract(layer),
+            LayerBlend::Screen => base.blend_screen(layer),
+        };
+        base.lerp(&blended, opacity)
+    }
+}
+
+/// Generador de ruido de `Uniforms` que usa un patrón.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NoiseSource {
+    OpenSimplex,
+    Cellular,
+}
+
+impl NoiseSource {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "open_simplex" => Some(NoiseSource::OpenSimplex),
+            "cellular" => Some(NoiseSource::Cellular),
+            _ => None,
+        }
+    }
+}
+
+/// Campo escalar en [0, 1] sobre la superficie, evaluado en espacio de modelo.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+    // Ruido fractal de `source`
+    Noise { source: NoiseSource, zoom: f32, octaves: u32, speed: f32 },
+    // `count` pares de bandas claras y oscuras de un polo al otro de `axis`, onduladas por ruido según `turbulence`
+    Bands { count: f32, axis: Vec3, turbulence: f32, speed: f32 },
+    // Celdas del ruido celular
+    Cells { zoom: f32, speed: f32 },
+    // Degradado de un polo (0) al otro (1) a lo largo de `axis`
+    Gradient { axis: Vec3 },
+}
+
+impl Pattern {
+    pub fn sample(&self, position: &Vec3, uniforms: &Uniforms) -> f32 {
+        let time = uniforms.time as f32;
+        let value = match self {
+            Pattern::Noise { source, zoom, octaves, speed } => {
+                let noise = match source {
+                    NoiseSource::OpenSimplex => &uniforms.noise_open_simplex,
+                    NoiseSource::Cellular => &uniforms.noise_cellular,
+                };
+                let noise = ruido_fractal(
+                    noise,
+                    position.x * zoom + time * speed,
+                    position.y * zoom,
+                    (*octaves).max(1),
+                    2.0,
+                    0.5,
+                );
+                noise * 0.5 + 0.5
+            }
+            Pattern::Bands { count, axis, turbulence, speed } => {
+                let warp = uniforms.noise_open_simplex
+                    .get_noise_2d(position.x * BAND_WARP_ZOOM + time * speed, position.z * BAND_WARP_ZOOM);
+                let distance = position.dot(&axis.normalize()) + warp * turbulence;
+                // `distance` recorre [-1, 1]: `count` periodos completos de polo a polo
+                (distance * count * PI + time * speed).sin() * 0.5 + 0.5
+            }
+            Pattern::Cells { zoom, speed } => {
+                let noise = uniforms.noise_cellular.get_noise_2d(position.x * zoom + time * speed, position.y * zoom);
+                noise * 0.5 + 0.5
+            }
+            Pattern::Gradient { axis } => position.normalize().dot(&axis.normalize()) * 0.5 + 0.5,
+        };
+        value.clamp(0.0, 1.0)
+    }
+}
+
+/// Capa de color: el valor del patrón muestrea `gradient` y el resultado se mezcla sobre las capas de abajo.
+#[derive(Debug, Clone)]
+pub struct Layer {
+    pub pattern: Pattern,
+    pub gradient: Gradient,
+    pub blend: LayerBlend,
+    pub opacity: f32,
+}
+
+impl Layer {
+    pub fn new(pattern: Pattern, gradient: Gradient) -> Self {
+        Layer {
+            pattern,
+            gradient,
+            blend: LayerBlend::Normal,
+            opacity: 1.0,
+        }
+    }
+
+    pub fn with_blend(self, blend: LayerBlend) -> Self {
+        Layer { blend, ..self }
+    }
+
+    pub fn with_opacity(self, opacity: f32) -> Self {
+        Layer { opacity, ..self }
+    }
+}
+
+/// Apariencia de un cuerpo como pila de capas, aplicadas en orden sobre `base`.
+#[derive(Debug, Clone)]
+pub struct LayerStack {
+    pub base: Color,
+    // Imagen leída con las coordenadas de textura de la malla; se multiplica por `base` debajo de las capas
+    pub texture: Option<(Arc<Texture>, Sampler)>,
+    pub layers: Vec<Layer>,
+    // Propiedades del material resultante
+    pub ambient: Color,
+    pub specular: f32,
+    pub roughness: f32,
+}
+
+impl LayerStack {
+    pub fn new(base: Color) -> Self {
+        LayerStack {
+            base,
+            texture: None,
+            layers: Vec::new(),
+            ambient: Color::black(),
+            specular: 0.0,
+            roughness: 1.0,
+        }
+    }
+
+    pub fn with_texture(self, texture: Arc<Texture>, sampler: Sampler) -> Self {
+        LayerStack { texture: Some((texture, sampler)), ..self }
+    }
+
+    pub fn with_layer(mut self, layer: Layer) -> Self {
+        self.layers.push(layer);
+        self
+    }
+
+    pub fn with_ambient(self, ambient: Color) -> Self {
+        LayerStack { ambient, ..self }
+    }
+
+    pub fn with_specular(self, specular: f32, roughness: f32) -> Self {
+        LayerStack { specular, roughness, ..self }
+    }
+
+    pub fn color_at(&self, fragment: &Fragment, uniforms: &Uniforms) -> Color {
+        let base = match &self.texture {
+            Some((texture, sampler)) => {
+                let (duv_dx, duv_dy) = fragment.tex_coords_slope;
+                texture.sample_grad(fragment.tex_coords, duv_dx, duv_dy, sampler).blend_multiply(&self.base)
+            }
+            None => self.base,
+        };
+
+        self.layers.iter().fold(base, |color, layer| {
+            let layer_color = layer.gradient.sample(layer.pattern.sample(&fragment.vertex_position, uniforms));
+            layer.blend.apply(&color, &layer_color, layer.opacity)
+        })
+    }
+}
+
+impl SurfaceShader for LayerStack {
+    fn surface(&self, fragment: &Fragment, uniforms: &Uniforms) -> Material {
+        Material::new(self.color_at(fragment, uniforms))
+            .with_ambient(self.ambient)
+            .with_specular(self.specular)
+            .with_roughness(self.roughness)
+    }
+
+    // Solo la textura elige el nivel de mipmap a partir de las derivadas
+    fn uses_texture_derivatives(&self) -> bool {
+        self.texture.is_some()
+    }
+}
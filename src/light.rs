@@ -0,0 +1,91 @@
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+
+// Cómo decae la luz con la distancia
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Falloff {
+    // Sin atenuación: la misma intensidad a cualquier distancia
+    None,
+    // Decae linealmente hasta llegar a cero en `range`
+    Linear { range: f32 },
+    // 1 / (1 + (d / radius)^2): inverso del cuadrado sin la singularidad en d = 0
+    InverseSquare { radius: f32 },
+}
+
+impl Falloff {
+    pub fn attenuation(&self, distance: f32) -> f32 {
+        match *self {
+            Falloff::None => 1.0,
+            Falloff::Linear { range } => (1.0 - distance / range).max(0.0),
+            Falloff::InverseSquare { radius } => 1.0 / (1.0 + (distance / radius).powi(2)),
+        }
+    }
+}
+
+/// Luz puntual definida en espacio de mundo.
+#[derive(Debug, Clone, Copy)]
+pub struct PointLight {
+    pub position: Vec3,
+    pub intensity: f32,
+    pub falloff: Falloff,
+}
+
+impl PointLight {
+    pub fn new(position: Vec3, intensity: f32, falloff: Falloff) -> Self {
+        PointLight { position, intensity, falloff }
+    }
+
+    /// La misma luz con su posición llevada a otro espacio, p. ej. al de la cámara.
+    pub fn transformed(&self, matrix: &Mat4) -> Self {
+        let position = matrix * Vec4::new(self.position.x, self.position.y, self.position.z, 1.0);
+        PointLight { position: position.xyz(), ..*self }
+    }
+}
+
+/// Intensidad difusa (Lambert) que reciben un punto y su normal, ambos en espacio de mundo.
+/// Sin luces el objeto se considera emisivo y no se atenúa.
+pub fn diffuse_intensity(lights: &[PointLight], position: &Vec3, normal: &Vec3) -> f32 {
+    if lights.is_empty() {
+        return 1.0;
+    }
+
+    lights.iter()
+        .map(|light| {
+            let to_light = light.position - position;
+            let distance = to_light.magnitude();
+            if distance <= f32::EPSILON {
+                return light.intensity;
+            }
+            let lambert = normal.dot(&(to_light / distance)).max(0.0);
+            lambert * light.intensity * light.falloff.attenuation(distance)
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_falloff_keeps_full_intensity() {
+        for distance in [0.0, 1.0, 1.0e6] {
+            assert_eq!(Falloff::None.attenuation(distance), 1.0);
+        }
+    }
+
+    #[test]
+    fn linear_falloff_reaches_zero_at_range() {
+        let falloff = Falloff::Linear { range: 100.0 };
+        assert_eq!(falloff.attenuation(0.0), 1.0);
+        assert_eq!(falloff.attenuation(25.0), 0.75);
+        assert_eq!(falloff.attenuation(100.0), 0.0);
+        assert_eq!(falloff.attenuation(250.0), 0.0);
+    }
+
+    #[test]
+    fn inverse_square_falloff_halves_at_radius() {
+        let falloff = Falloff::InverseSquare { radius: 800.0 };
+        assert_eq!(falloff.attenuation(0.0), 1.0);
+        assert_eq!(falloff.attenuation(800.0), 0.5);
+        assert_eq!(falloff.attenuation(2400.0), 0.1);
+    }
+}
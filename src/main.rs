@@ -1,6 +1,8 @@
-use nalgebra_glm::{Vec2, Vec3, Vec4, Mat4};
+use nalgebra_glm::{Vec3, Vec4, Mat4};
 use minifb::{Key, Window, WindowOptions};
-use std::time::Duration;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 mod framebuffer;
 mod triangle;
@@ -13,76 +15,138 @@ mod shaders;
 mod experimental_shaders;
 mod camera;
 mod skybox;
+mod headless;
+mod options;
+mod clipping;
+mod pipeline;
+mod rasterizer;
+mod tonemap;
+mod light;
+mod shadow;
+mod atmosphere;
+mod bloom;
+mod material;
+mod flare;
+mod shader_registry;
+mod layers;
+mod shader_config;
+mod scene_config;
+mod hot_reload;
+mod noise;
+mod texture;
+mod math;
 
 use framebuffer::Framebuffer;
 use vertex::Vertex;
 use obj::Obj;
-use triangle::triangle;
 use shaders::vertex_shader;
-use fastnoise_lite::{FastNoiseLite, NoiseType, CellularDistanceFunction};
 use nalgebra_glm as glm;
 use skybox::Skybox;
-use crate::fragment::{fragment_shader, Fragment, ring_shader};
 use crate::color::Color;
-use crate::camera::Camera;
+use crate::camera::{Camera, Projection};
 use crate::line::draw_circle;
+use crate::options::{Options, RenderSettings};
+use crate::clipping::{clip_triangle, project_to_screen};
+use crate::pipeline::{BlendMode, CullMode, RenderState};
+use crate::rasterizer::rasterize;
+use crate::light::{Falloff, PointLight};
+use crate::shadow::ShadowMap;
+use crate::atmosphere::Atmosphere;
+use crate::material::LightingModel;
+use crate::shader_registry::{FragmentShader, ShaderRegistry, Unlit};
+use crate::scene_config::PlanetConfig;
+use crate::noise::{BodyNoise, BodyNoiseConfig, Noise};
+use crate::hot_reload::FileWatcher;
 
 pub struct Uniforms {
     model_matrix: Mat4,
     view_matrix: Mat4,
     projection_matrix: Mat4,
+    // Tipo de `projection_matrix`
+    projection: Projection,
     viewport_matrix: Mat4,
     time: u32,
-    noise_open_simplex: FastNoiseLite,
-    noise_cellular: FastNoiseLite,
+    // Generadores de ruido del cuerpo
+    noise_open_simplex: Arc<Noise>,
+    noise_cellular: Arc<Noise>,
+    // Luces en el mismo espacio que `model_matrix`; vacío para objetos que no se iluminan
+    lights: Vec<PointLight>,
+    // Cámara en el mismo espacio que `model_matrix`
+    camera_position: Vec3,
+    camera_direction: Vec3,
+    // Sombras de la luz del Sol sobre este cuerpo, si las hay
+    shadow_map: Option<Arc<ShadowMap>>,
+    // Parámetros de dispersión para el shader "atmosphere"
+    atmosphere: Option<Atmosphere>,
+    // Modelo de iluminación de la etapa de sombreado común
+    lighting: LightingModel,
 }
 
-pub struct Moon {
-    pub position: Vec3,
-    pub scale: f32,
-    pub rotation: Vec3,
+impl Uniforms {
+    // Dirección unitaria desde un punto hacia la cámara; con proyección ortográfica es la misma en todo punto
+    fn view_direction(&self, world_position: &Vec3) -> Vec3 {
+        match self.projection {
+            Projection::Orthographic => -self.camera_direction,
+            Projection::Perspective => (self.camera_position - world_position).normalize(),
+        }
+    }
 }
 
 struct Planet {
-    pub name: &'static str,
     pub scale: f32,
     pub orbit_radius: f32,
     pub orbit_speed: f32,
     pub rotation_speed: f32,
-    pub shader: &'static str,
+    pub shader: Arc<dyn FragmentShader>,
+    pub atmosphere: Option<Atmosphere>,
+    pub noise: BodyNoise,
+    pub rings: bool,
+    pub rings_noise: BodyNoise,
+    pub moon: bool,
+    pub moon_noise: BodyNoise,
 }
 
-fn create_uniforms() -> Uniforms {
-    let mut noise_open_simplex = FastNoiseLite::with_seed(1337);
-    noise_open_simplex.set_noise_type(Some(NoiseType::OpenSimplex2));
-    
-    let mut noise_cellular = FastNoiseLite::with_seed(1337);
-    noise_cellular.set_noise_type(Some(NoiseType::Cellular));
-    noise_cellular.set_cellular_distance_function(Some(CellularDistanceFunction::Manhattan));
+impl Planet {
+    fn from_config(config: &PlanetConfig, shaders: &ShaderRegistry) -> Result<Self, String> {
+        let noise = config.noise.build();
+        // Sin ruido propio, la luna y los anillos comparten los generadores del planeta
+        let companion_noise = |own: &Option<BodyNoiseConfig>| own.map(|own| own.build()).unwrap_or_else(|| noise.clone());
+
+        Ok(Planet {
+            scale: config.scale,
+            orbit_radius: config.orbit_radius,
+            orbit_speed: config.orbit_speed,
+            rotation_speed: config.rotation_speed,
+            shader: shaders.get(&config.shader).map_err(|err| format!("planet '{}': {}", config.name, err))?,
+            atmosphere: config.atmosphere,
+            rings: config.rings,
+            rings_noise: companion_noise(&config.rings_noise),
+            moon: config.moon,
+            moon_noise: companion_noise(&config.moon_noise),
+            noise,
+        })
+    }
+}
 
+fn create_uniforms(noise: &BodyNoise) -> Uniforms {
     Uniforms {
         model_matrix: Mat4::identity(),
         view_matrix: Mat4::identity(),
         projection_matrix: Mat4::identity(),
+        projection: Projection::Orthographic,
         viewport_matrix: Mat4::identity(),
         time: 0,
-        noise_open_simplex,
-        noise_cellular
+        noise_open_simplex: noise.open_simplex.clone(),
+        noise_cellular: noise.cellular.clone(),
+        lights: Vec::new(),
+        camera_position: Vec3::new(0.0, 0.0, 0.0),
+        camera_direction: Vec3::new(0.0, 0.0, 1.0),
+        shadow_map: None,
+        atmosphere: None,
+        lighting: LightingModel::BlinnPhong,
     }
 }
 
-fn create_open_simplex_noise() -> FastNoiseLite {
-    let mut noise = FastNoiseLite::with_seed(1337);
-    noise.set_noise_type(Some(NoiseType::OpenSimplex2));
-    noise
-}
-
-fn create_cellular_noise() -> FastNoiseLite {
-    let mut noise = FastNoiseLite::with_seed(1337);
-    noise.set_noise_type(Some(NoiseType::Cellular));
-    noise
-}
-
 fn create_model_matrix(translation: Vec3, scale: f32, rotation: Vec3) -> Mat4 {
     let rotation_matrix_x = nalgebra_glm::rotation(rotation.x, &Vec3::x_axis());
     let rotation_matrix_y = nalgebra_glm::rotation(rotation.y, &Vec3::y_axis());
@@ -93,100 +157,10 @@ fn create_model_matrix(translation: Vec3, scale: f32, rotation: Vec3) -> Mat4 {
     let scaling_matrix = nalgebra_glm::scaling(&Vec3::new(scale, scale, scale));
     let translation_matrix = nalgebra_glm::translation(&translation);
 
-    let model_matrix = translation_matrix * rotation_matrix * scaling_matrix;
-
-    model_matrix
-}
-
-fn create_view_matrix(translation: Vec3, rotation: Vec3, scale: f32) -> Mat4 {
-    let translation_matrix = nalgebra_glm::translation(&-translation);
-    let scaling_matrix = nalgebra_glm::scaling(&Vec3::new(1.0 / scale, 1.0 / scale, 1.0 / scale));
-
-    let rotation_matrix_x = nalgebra_glm::rotation(-rotation.x, &Vec3::x_axis());
-    let rotation_matrix_y = nalgebra_glm::rotation(-rotation.y, &Vec3::y_axis());
-    let rotation_matrix_z = nalgebra_glm::rotation(-rotation.z, &Vec3::z_axis());
-
-    let rotation_matrix = rotation_matrix_z * rotation_matrix_y * rotation_matrix_x;
-
-    let view_matrix = scaling_matrix * rotation_matrix * translation_matrix;
-
-    view_matrix
-}
-
-fn update_starship_position_relative_to_camera(
-    camera_translation: Vec3, 
-    camera_rotation: Vec3, 
-    starship_offset: Vec3,
-) -> (Vec3, Vec3) {
-    let starship_translation = camera_translation + starship_offset;
-
-    let starship_rotation = camera_rotation;
-
-    (starship_translation, starship_rotation)
-}
-
-fn define_planets() -> Vec<Planet> {
-    vec![
-        Planet {
-            name: "ROCKY_PLANET",
-            scale: 5.4,
-            orbit_radius: 100.0,
-            orbit_speed: 0.02,
-            rotation_speed: 0.01,
-            shader: "rocky_planet_shader",
-        },
-        Planet {
-            name: "PLANET_COLORFUL",
-            scale: 6.8,
-            orbit_radius: 180.0,
-            orbit_speed: 0.015,
-            rotation_speed: 0.008,
-            shader: "colorful",
-        },
-        Planet {
-            name: "ROCKY_PLANET_WITH_MOON",
-            scale: 6.0,
-            orbit_radius: 260.0,
-            orbit_speed: 0.01,
-            rotation_speed: 0.006,
-            shader: "rocky_planet_with_moon_shader",
-        },
-        Planet {
-            name: "DARK_RED",
-            scale: 7.4,
-            orbit_radius: 340.0,
-            orbit_speed: 0.005,
-            rotation_speed: 0.004,
-            shader: "dark_red",
-        },
-        Planet {
-            name: "GAS_GIANT",
-            scale: 12.0,
-            orbit_radius: 440.0,
-            orbit_speed: 0.002,
-            rotation_speed: 0.0008,
-            shader: "gas_giant_shader",
-        },
-        Planet {
-            name: "PLANET_EXOTIC",
-            scale: 8.0,
-            orbit_radius: 530.0,
-            orbit_speed: 0.003,
-            rotation_speed: 0.002,
-            shader: "exotic",
-        },
-        Planet {
-            name: "GAS_GIANT_WITH_RINGS",
-            scale: 9.4,
-            orbit_radius: 650.0,
-            orbit_speed: 0.001,
-            rotation_speed: 0.0008,
-            shader: "gas_giant_with_rings",
-        }
-    ]
+    translation_matrix * rotation_matrix * scaling_matrix
 }
 
-fn render(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertex_array: &[Vertex], shader_type: &str) {
+fn render(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertex_array: &[Vertex], shader: &dyn FragmentShader, state: RenderState) {
     // Vertex Shader Stage
     let mut transformed_vertices = Vec::with_capacity(vertex_array.len());
     for vertex in vertex_array {
@@ -194,80 +168,173 @@ fn render(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertex_array: &[Ve
         transformed_vertices.push(transformed);
     }
 
+    // Recorte en espacio de clip, luego división por `w`, viewport y descarte de caras
     let mut triangles = Vec::new();
     for i in (0..transformed_vertices.len()).step_by(3) {
         if i + 2 < transformed_vertices.len() {
-            triangles.push([
-                transformed_vertices[i].clone(),
-                transformed_vertices[i + 1].clone(),
-                transformed_vertices[i + 2].clone(),
-            ]);
+            for clipped in clip_triangle(
+                &transformed_vertices[i],
+                &transformed_vertices[i + 1],
+                &transformed_vertices[i + 2],
+            ) {
+                let screen = clipped.map(|v| project_to_screen(&v, &uniforms.viewport_matrix));
+                if !state.is_culled(
+                    &screen[0].transformed_position,
+                    &screen[1].transformed_position,
+                    &screen[2].transformed_position,
+                ) {
+                    triangles.push(screen);
+                }
+            }
         }
     }
 
-    let mut fragments = Vec::new();
-    for tri in &triangles {
-        fragments.extend(triangle(&tri[0], &tri[1], &tri[2]));
-    }
+    // Rasterización y sombreado por tiles
+    rasterize(framebuffer, &triangles, uniforms, shader, state);
+}
 
-    for fragment in fragments {
-        let x = fragment.position.x as usize;
-        let y = fragment.position.y as usize;
-        if x < framebuffer.width && y < framebuffer.height {
-            let shaded_color = fragment_shader(&fragment, uniforms, shader_type);
-            let color = shaded_color.to_hex();
-            framebuffer.set_current_color(color);
-            framebuffer.point(x, y, fragment.depth);
-        }
-    }
-    
+// Radio de la esfera centrada en el origen del modelo que contiene a toda la malla
+fn bounding_radius(vertex_array: &[Vertex]) -> f32 {
+    vertex_array.iter().map(|vertex| vertex.position.magnitude()).fold(0.0, f32::max)
 }
 
-fn main() {
-    let window_width = 800;
-    let window_height = 600;
-    let framebuffer_width = 800;
-    let framebuffer_height = 600;
-    let frame_delay = Duration::from_millis(16);
+// Mapa de sombras desde el Sol que abarca a todos los `casters` (matriz de modelo, malla y su radio
+// según `bounding_radius`) del cuerpo con `noise`
+fn render_shadow_map(light_position: Vec3, target: Vec3, casters: &[(Mat4, &[Vertex], f32)], noise: &BodyNoise) -> ShadowMap {
+    // Radio de la esfera que contiene a todos los cuerpos, alrededor de `target`: la esfera de cada malla
+    // se lleva al mundo con su matriz de modelo, usando la mayor escala de sus ejes
+    let radius = casters.iter()
+        .map(|(model_matrix, _, mesh_radius)| {
+            let center = (model_matrix * Vec4::new(0.0, 0.0, 0.0, 1.0)).xyz();
+            let scale = (0..3)
+                .map(|axis| model_matrix.fixed_view::<3, 1>(0, axis).magnitude())
+                .fold(0.0, f32::max);
+            (center - target).magnitude() + mesh_radius * scale
+        })
+        .fold(0.0, f32::max);
+
+    let mut shadow_map = ShadowMap::new(light_position, target, radius);
+    shadow_map.depth.clear();
+
+    for (model_matrix, vertex_array, _) in casters {
+        let mut uniforms = create_uniforms(noise);
+        uniforms.model_matrix = *model_matrix;
+        uniforms.view_matrix = shadow_map.view_matrix;
+        uniforms.projection_matrix = shadow_map.projection_matrix;
+        uniforms.projection = Projection::Orthographic;
+        uniforms.viewport_matrix = shadow_map.viewport_matrix;
+
+        // Solo se escribe profundidad: el shader no llega a ejecutarse
+        render(&mut shadow_map.depth, &uniforms, vertex_array, &Unlit(|_, _| Color::black()), RenderState::depth_only());
+    }
 
-    let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
-    let mut window = Window::new(
-        "Planetary System",
-        window_width,
-        window_height,
-        WindowOptions::default(),
-    )
-    .unwrap();
+    shadow_map
+}
 
-    window.set_position(500, 500);
-    window.update();
+// Dibujo diferido para la pasada translúcida, que se ordena de atrás hacia adelante
+struct DrawCall<'a> {
+    uniforms: Uniforms,
+    vertex_array: &'a [Vertex],
+    shader: &'a dyn FragmentShader,
+    state: RenderState,
+}
 
-    //framebuffer.set_background_color(0x000000);
-    let skybox = Skybox::new(5000);
+impl DrawCall<'_> {
+    // Profundidad en vista del origen del modelo; mayor es más lejos
+    fn view_depth(&self) -> f32 {
+        (self.uniforms.view_matrix * self.uniforms.model_matrix * Vec4::new(0.0, 0.0, 0.0, 1.0)).z
+    }
+}
 
-    let planets = define_planets();
-    let mut camera = Camera::new();
+// Cuerpos, shaders y ruido que salen de los archivos de configuración; se pueden recargar con el programa abierto
+struct Bodies {
+    planets: Vec<Planet>,
+    // Ruido del fondo, y del Sol y de la nave si no tienen uno propio
+    noise: BodyNoise,
+    sun_noise: BodyNoise,
+    starship_noise: BodyNoise,
+    sun_falloff: Falloff,
+    sun_shader: Arc<dyn FragmentShader>,
+    ring_shader: Arc<dyn FragmentShader>,
+    moon_shader: Arc<dyn FragmentShader>,
+    starship_shader: Arc<dyn FragmentShader>,
+    atmosphere_shader: Arc<dyn FragmentShader>,
+}
 
-    let planet_obj = Obj::load("assets/spheresmooth.obj").expect("Failed to load obj");
-    let planet_vertex_array = planet_obj.get_vertex_array();
+impl Bodies {
+    // Lee `scene_config::SCENE_PATH` y `shader_config::SHADERS_PATH`; falla si algún cuerpo usa un shader que no está registrado
+    fn load() -> Result<Self, String> {
+        let mut shaders = ShaderRegistry::with_builtin_shaders();
+        shader_config::load_shader_definitions(Path::new(shader_config::SHADERS_PATH), &mut shaders)?;
+        let config = scene_config::load_scene_config(Path::new(scene_config::SCENE_PATH))?;
+        let noise = config.noise.build();
+        let own_noise = |own: &Option<BodyNoiseConfig>| own.map(|own| own.build()).unwrap_or_else(|| noise.clone());
+
+        Ok(Bodies {
+            planets: config.planets
+                .iter()
+                .map(|planet| Planet::from_config(planet, &shaders))
+                .collect::<Result<Vec<_>, _>>()?,
+            sun_noise: own_noise(&config.sun_noise),
+            starship_noise: own_noise(&config.starship_noise),
+            noise,
+            sun_falloff: config.sun_falloff,
+            sun_shader: shaders.get("solar_surface")?,
+            ring_shader: shaders.get("ring")?,
+            moon_shader: shaders.get("moon_shader")?,
+            starship_shader: shaders.get("starship_shader")?,
+            atmosphere_shader: shaders.get("atmosphere")?,
+        })
+    }
+}
 
-    let ring_obj = Obj::load("assets/rings.obj").expect("Failed to load rings.obj");
-    let ring_vertex_array = ring_obj.get_vertex_array();
-    
-    let starship_obj = Obj::load("assets/ZyronStarship.obj").expect("Failed to load starship.obj");
-    let starship_vertex_array = starship_obj.get_vertex_array();
+pub struct Scene {
+    skybox: Skybox,
+    bodies: Bodies,
+    planet_vertex_array: Vec<Vertex>,
+    ring_vertex_array: Vec<Vertex>,
+    starship_vertex_array: Vec<Vertex>,
+    // Radios de las mallas que proyectan sombra, calculados al cargarlas
+    planet_radius: f32,
+    ring_radius: f32,
+}
 
-    let mut time = 0;
+impl Scene {
+    /// Carga mallas, shaders y planetas; falla si algún archivo de configuración no es válido.
+    fn load() -> Result<Self, String> {
+        let planet_obj = Obj::load("assets/spheresmooth.obj").expect("Failed to load obj");
+        let ring_obj = Obj::load("assets/rings.obj").expect("Failed to load rings.obj");
+        let starship_obj = Obj::load("assets/ZyronStarship.obj").expect("Failed to load starship.obj");
+
+        let planet_vertex_array = planet_obj.get_vertex_array();
+        let ring_vertex_array = ring_obj.get_vertex_array();
+
+        Ok(Scene {
+            skybox: Skybox::new(5000),
+            bodies: Bodies::load()?,
+            planet_radius: bounding_radius(&planet_vertex_array),
+            ring_radius: bounding_radius(&ring_vertex_array),
+            planet_vertex_array,
+            ring_vertex_array,
+            starship_vertex_array: starship_obj.get_vertex_array(),
+        })
+    }
 
-    let mut camera_translation = Vec3::new(0.0, 0.0, 0.0);
-    let mut camera_rotation = Vec3::new(0.0, 0.0, 0.0);
-    let mut camera_scale = 1.0f32;
+    /// Vuelve a leer los archivos de configuración sin recargar las mallas.
+    /// Si algo falla, la escena queda como estaba.
+    fn reload(&mut self) -> Result<(), String> {
+        self.bodies = Bodies::load()?;
+        Ok(())
+    }
+}
 
-    let mut moon = Moon {
-        position: Vec3::new(0.0, 0.0, 0.0),
-        scale: 8.0,
-        rotation: Vec3::new(0.0, 0.0, 0.0),
-    };
+pub fn render_scene(framebuffer: &mut Framebuffer, scene: &Scene, camera: &Camera, time: u32, settings: &RenderSettings) {
+    let framebuffer_width = framebuffer.width;
+    let framebuffer_height = framebuffer.height;
+    let center_x = framebuffer_width as f32 / 2.0;
+    let center_y = framebuffer_height as f32 / 2.0;
+
+    framebuffer.clear();
 
     let skybox_uniforms = Uniforms {
         model_matrix: Mat4::identity(),
@@ -277,244 +344,372 @@ fn main() {
             45.0_f32.to_radians(),
             0.1,
             2000.0,
-        ),        
-        viewport_matrix: nalgebra_glm::scaling(&Vec3::new(
-            framebuffer_width as f32 / 2.0,
-            framebuffer_height as f32 / 2.0,
-            1.0,
-        )),
+        ),
+        projection: Projection::Perspective,
+        viewport_matrix: nalgebra_glm::scaling(&Vec3::new(center_x, center_y, 1.0)),
         time,
-        noise_open_simplex: create_open_simplex_noise(),
-        noise_cellular: create_cellular_noise()
+        noise_open_simplex: scene.bodies.noise.open_simplex.clone(),
+        noise_cellular: scene.bodies.noise.cellular.clone(),
+        lights: Vec::new(),
+        camera_position: Vec3::new(0.0, 0.0, 0.0),
+        camera_direction: Vec3::new(0.0, 0.0, -1.0),
+        shadow_map: None,
+        atmosphere: None,
+        lighting: LightingModel::BlinnPhong,
     };
 
-    while window.is_open() && !window.is_key_down(Key::Escape) {
-        handle_camera_input(&mut camera, &window);
-        time += 1;
-
-        framebuffer.clear();       
-
-        skybox.render(&mut framebuffer, &skybox_uniforms, camera_translation);
-
-        let view_matrix = camera.view_matrix();
+    scene.skybox.render(framebuffer, &skybox_uniforms, Vec3::new(0.0, 0.0, 0.0));
+
+    let view_matrix = camera.view_matrix();
+    let projection_matrix = camera.projection_matrix(framebuffer_width, framebuffer_height);
+    let projection = camera.projection();
+    let viewport_matrix = camera.viewport_matrix(framebuffer_width, framebuffer_height);
+    let camera_position = camera.position();
+    let camera_direction = camera.direction();
+
+    // Renderizar el Sol
+    let sun_translation = Vec3::new(center_x, center_y, 0.0);
+    let sun_scale = 40.0;
+    let sun_rotation = Vec3::new(0.0, 0.0, time as f32 * 0.5);
+    let sun_model_matrix = create_model_matrix(sun_translation, sun_scale, sun_rotation);
+
+    let mut sun_uniforms = create_uniforms(&scene.bodies.sun_noise);
+    sun_uniforms.model_matrix = sun_model_matrix;
+    sun_uniforms.view_matrix = view_matrix;
+    sun_uniforms.projection_matrix = projection_matrix;
+    sun_uniforms.projection = projection;
+    sun_uniforms.viewport_matrix = viewport_matrix;
+    sun_uniforms.time = time;
+    sun_uniforms.camera_position = camera_position;
+    sun_uniforms.camera_direction = camera_direction;
+
+    render(
+        framebuffer,
+        &sun_uniforms,
+        &scene.planet_vertex_array,
+        scene.bodies.sun_shader.as_ref(),
+        RenderState::opaque(),
+    );
+
+    // El Sol ilumina al resto de los cuerpos como una luz puntual en su centro
+    let sun_light = PointLight::new(sun_translation, 1.0, scene.bodies.sun_falloff);
+
+    let mut transparent_draws = Vec::new();
+
+    // Renderizar proyecto
+    for planet in &scene.bodies.planets {
+        let angle = time as f32 * planet.orbit_speed;
+
+        let planet_translation = Vec3::new(
+            center_x + planet.orbit_radius * angle.cos(),
+            center_y + planet.orbit_radius * angle.sin(),
+            0.0,
+        );
 
-        // Renderizar el Sol
-        let sun_translation = Vec3::new(
-            window_width as f32 / 2.0, 
-            window_height as f32 / 2.0, 
-            0.0
+        let planet_rotation = Vec3::new(0.0, time as f32 * planet.rotation_speed, 0.0);
+        let planet_model_matrix = create_model_matrix(
+            planet_translation,
+            planet.scale * 2.0,
+            planet_rotation,
         );
-        let sun_scale = 40.0;
-        let sun_rotation = Vec3::new(0.0, 0.0, time as f32 * 0.5);
-        let sun_model_matrix = create_model_matrix(sun_translation, sun_scale, sun_rotation);
 
-        let mut sun_uniforms = create_uniforms();
-        sun_uniforms.model_matrix = sun_model_matrix;
-        sun_uniforms.view_matrix = view_matrix;
-        sun_uniforms.time = time;
+        // Si el planeta tiene anillos
+        let ring_model_matrix = if planet.rings {
+            // Configurar los anillos
+            let ring_scale = planet.scale * 2.5;
 
-        render(
-            &mut framebuffer,
-            &sun_uniforms,
-            &planet_vertex_array,
-            "solar_surface",
-        );
+            // Inclinar los anillos
+            let rotation_inclination = nalgebra_glm::rotation(45.0_f32.to_radians(), &Vec3::x_axis());
+            Some(create_model_matrix(planet_translation, ring_scale, planet_rotation) * rotation_inclination)
+        } else {
+            None
+        };
 
-        // Renderizar proyecto
-        for planet in &planets {
-            let angle = time as f32 * planet.orbit_speed;
+        // Si el planeta tiene luna
+        let moon_model_matrix = if planet.moon {
+            let moon_orbit_radius = 30.0;
+            let moon_scale = planet.scale * 0.8;
+            let moon_orbit_speed = 0.02;
+            let moon_angle = time as f32 * moon_orbit_speed;
 
-            let planet_translation = Vec3::new(
-                (window_width as f32 / 2.0) + planet.orbit_radius * angle.cos(),
-                (window_height as f32 / 2.0) + planet.orbit_radius * angle.sin(),
+            let moon_translation = Vec3::new(
+                planet_translation.x + moon_orbit_radius * moon_angle.cos(),
+                planet_translation.y + moon_orbit_radius * moon_angle.sin(),
                 0.0,
             );
 
-            let planet_rotation = Vec3::new(0.0, time as f32 * planet.rotation_speed, 0.0);
-            let planet_model_matrix = create_model_matrix(
+            // Rotación de la luna
+            let moon_rotation_speed = planet.rotation_speed * 0.3;
+            let moon_rotation = Vec3::new(0.0, time as f32 * moon_rotation_speed, 0.0);
+
+            Some(create_model_matrix(
+                moon_translation,
+                moon_scale,
+                moon_rotation,
+            ))
+        } else {
+            None
+        };
+
+        // Con luna o anillos, los cuerpos del sistema pueden hacerse sombra entre sí
+        let mut casters = vec![(planet_model_matrix, scene.planet_vertex_array.as_slice(), scene.planet_radius)];
+        casters.extend(ring_model_matrix.map(|m| (m, scene.ring_vertex_array.as_slice(), scene.ring_radius)));
+        casters.extend(moon_model_matrix.map(|m| (m, scene.planet_vertex_array.as_slice(), scene.planet_radius)));
+        let shadow_map = if casters.len() > 1 {
+            Some(Arc::new(render_shadow_map(sun_translation, planet_translation, &casters, &planet.noise)))
+        } else {
+            None
+        };
+
+        let mut planet_uniforms = create_uniforms(&planet.noise);
+        planet_uniforms.model_matrix = planet_model_matrix;
+        planet_uniforms.view_matrix = view_matrix;
+        planet_uniforms.projection_matrix = projection_matrix;
+        planet_uniforms.projection = projection;
+        planet_uniforms.viewport_matrix = viewport_matrix;
+        planet_uniforms.time = time;
+        planet_uniforms.lights = vec![sun_light];
+        planet_uniforms.camera_position = camera_position;
+        planet_uniforms.camera_direction = camera_direction;
+        planet_uniforms.shadow_map = shadow_map.clone();
+        planet_uniforms.lighting = settings.lighting;
+
+        // Renderizar órbita
+        const ORBIT_COLOR: Color = Color::new(200, 200, 200);
+
+        draw_circle(
+            framebuffer,
+            Vec3::new(center_x, center_y, 0.0),
+            planet.orbit_radius,
+            ORBIT_COLOR,
+            view_matrix,
+        );
+
+        // Renderizar planeta
+        render(
+            framebuffer,
+            &planet_uniforms,
+            &scene.planet_vertex_array,
+            planet.shader.as_ref(),
+            RenderState::opaque(),
+        );
+
+        // La atmósfera es una esfera algo mayor que suma la luz dispersada en la pasada translúcida
+        if let Some(atmosphere) = planet.atmosphere {
+            let mut atmosphere_uniforms = create_uniforms(&planet.noise);
+            atmosphere_uniforms.model_matrix = create_model_matrix(
                 planet_translation,
-                planet.scale * 2.0,
+                planet.scale * 2.0 * (1.0 + atmosphere.height),
                 planet_rotation,
             );
+            atmosphere_uniforms.view_matrix = view_matrix;
+            atmosphere_uniforms.projection_matrix = projection_matrix;
+            atmosphere_uniforms.projection = projection;
+            atmosphere_uniforms.viewport_matrix = viewport_matrix;
+            atmosphere_uniforms.time = time;
+            atmosphere_uniforms.lights = vec![sun_light];
+            atmosphere_uniforms.camera_position = camera_position;
+            atmosphere_uniforms.camera_direction = camera_direction;
+            atmosphere_uniforms.atmosphere = Some(atmosphere);
+
+            // Solo la cara frontal de la capa, para no sumar la luz dos veces
+            transparent_draws.push(DrawCall {
+                uniforms: atmosphere_uniforms,
+                vertex_array: &scene.planet_vertex_array,
+                shader: scene.bodies.atmosphere_shader.as_ref(),
+                state: RenderState::transparent()
+                    .with_blend(BlendMode::Additive)
+                    .with_cull_mode(CullMode::Back),
+            });
+        }
 
-            let mut planet_uniforms = create_uniforms();
-            planet_uniforms.model_matrix = planet_model_matrix;
-            planet_uniforms.view_matrix = view_matrix;
-            planet_uniforms.time = time;
-
-            // Renderizar órbita
-            const ORBIT_COLOR: Color = Color::new(200, 200, 200);
-
-            draw_circle(
-                &mut framebuffer,
-                Vec3::new(
-                    window_width as f32 / 2.0,
-                    window_height as f32 / 2.0,
-                    0.0,
-                ),
-                planet.orbit_radius,
-                ORBIT_COLOR,
-                view_matrix,
-            );
+        if let Some(ring_model_matrix) = ring_model_matrix {
+            let mut ring_uniforms = create_uniforms(&planet.rings_noise);
+            ring_uniforms.model_matrix = ring_model_matrix;
+            ring_uniforms.view_matrix = view_matrix;
+            ring_uniforms.projection_matrix = projection_matrix;
+            ring_uniforms.projection = projection;
+            ring_uniforms.viewport_matrix = viewport_matrix;
+            ring_uniforms.time = time;
+            ring_uniforms.lights = vec![sun_light];
+            ring_uniforms.camera_position = camera_position;
+            ring_uniforms.camera_direction = camera_direction;
+            ring_uniforms.shadow_map = shadow_map.clone();
+            ring_uniforms.lighting = settings.lighting;
+
+            // Los anillos son translúcidos: se dibujan después de la geometría opaca
+            transparent_draws.push(DrawCall {
+                uniforms: ring_uniforms,
+                vertex_array: &scene.ring_vertex_array,
+                shader: scene.bodies.ring_shader.as_ref(),
+                state: RenderState::transparent(),
+            });
+        }
+
+        if let Some(moon_model_matrix) = moon_model_matrix {
+            let mut moon_uniforms = create_uniforms(&planet.moon_noise);
+            moon_uniforms.model_matrix = moon_model_matrix;
+            moon_uniforms.view_matrix = view_matrix;
+            moon_uniforms.projection_matrix = projection_matrix;
+            moon_uniforms.projection = projection;
+            moon_uniforms.viewport_matrix = viewport_matrix;
+            moon_uniforms.time = time;
+            moon_uniforms.lights = vec![sun_light];
+            moon_uniforms.camera_position = camera_position;
+            moon_uniforms.camera_direction = camera_direction;
+            moon_uniforms.shadow_map = shadow_map.clone();
+            moon_uniforms.lighting = settings.lighting;
 
-            // Renderizar planeta
             render(
-                &mut framebuffer,
-                &planet_uniforms,
-                &planet_vertex_array,
-                planet.shader,
+                framebuffer,
+                &moon_uniforms,
+                &scene.planet_vertex_array,
+                scene.bodies.moon_shader.as_ref(),
+                RenderState::opaque(),
             );
-
-            // Si el planeta tiene anillos
-            if planet.name == "GAS_GIANT_WITH_RINGS" {
-                // Configurar los anillos
-                let ring_scale = planet.scale * 2.5;
-            
-                // Inclinar los anillos
-                let rotation_inclination = nalgebra_glm::rotation(45.0_f32.to_radians(), &Vec3::x_axis());
-                let ring_model_matrix = create_model_matrix(planet_translation, ring_scale, planet_rotation)
-                    * rotation_inclination;
-            
-                let mut ring_uniforms = create_uniforms();
-                ring_uniforms.model_matrix = ring_model_matrix;
-                ring_uniforms.view_matrix = view_matrix;
-                ring_uniforms.time = time;
-            
-                // Renderizar los anillos
-                render(
-                    &mut framebuffer,
-                    &ring_uniforms,
-                    &ring_vertex_array,
-                    "ring",
-                );
-            }                      
-
-            // Si el planeta tiene luna
-            if planet.name == "ROCKY_PLANET_WITH_MOON" {
-                let moon_orbit_radius = 30.0;
-                let moon_scale = planet.scale * 0.8;
-                let moon_orbit_speed = 0.02;
-                let moon_angle = time as f32 * moon_orbit_speed;
-
-                let moon_translation = Vec3::new(
-                    planet_translation.x + moon_orbit_radius * moon_angle.cos(),
-                    planet_translation.y + moon_orbit_radius * moon_angle.sin(),
-                    0.0,
-                );
-
-                // Rotación de la luna
-                let moon_rotation_speed = planet.rotation_speed * 0.3;
-                let moon_rotation = Vec3::new(0.0, time as f32 * moon_rotation_speed, 0.0);
-
-                let moon_model_matrix = create_model_matrix(
-                    moon_translation,
-                    moon_scale,
-                    moon_rotation,
-                );
-
-                let mut moon_uniforms = create_uniforms();
-                moon_uniforms.model_matrix = moon_model_matrix;
-                moon_uniforms.view_matrix = view_matrix;
-                moon_uniforms.time = time;
-
-                render(
-                    &mut framebuffer,
-                    &moon_uniforms,
-                    &planet_vertex_array,
-                    "moon_shader",
-                );
-            }
         }
+    }
 
-        // Renderizar la nave
-        let starship_translation = Vec3::new(
-            window_width as f32 / 2.0,
-            window_height as f32 / 2.0 + 200.0, 
-            -100.0,
-        );
-        let starship_scale = 30.0;
-        let starship_rotation = Vec3::new(0.0, -1.5, 3.1); 
-    
-        let starship_model_matrix = create_model_matrix(starship_translation, starship_scale, starship_rotation);
-    
-        let starship_uniforms = Uniforms {
-            model_matrix: starship_model_matrix,
-            view_matrix: Mat4::identity(), 
-            projection_matrix: Mat4::identity(), 
-            viewport_matrix: Mat4::identity(),
-            time,
-            noise_open_simplex: create_open_simplex_noise(),
-            noise_cellular: create_cellular_noise(),
-        };
+    // Renderizar la nave
+    let starship_translation = Vec3::new(center_x, center_y + 200.0, -100.0);
+    let starship_scale = 30.0;
+    let starship_rotation = Vec3::new(0.0, -1.5, 3.1);
 
-        render(
-            &mut framebuffer,
-            &starship_uniforms,
-            &starship_vertex_array,
-            "starship_shader",
-        );
+    let starship_model_matrix = create_model_matrix(starship_translation, starship_scale, starship_rotation);
 
-        window
-            .update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height)
-            .unwrap();
+    let starship_uniforms = Uniforms {
+        model_matrix: starship_model_matrix,
+        view_matrix: Mat4::identity(),
+        projection_matrix,
+        projection,
+        viewport_matrix,
+        time,
+        noise_open_simplex: scene.bodies.starship_noise.open_simplex.clone(),
+        noise_cellular: scene.bodies.starship_noise.cellular.clone(),
+        // La nave se ubica en espacio de cámara, así que la luz también
+        lights: vec![sun_light.transformed(&view_matrix)],
+        camera_position: Vec3::new(0.0, 0.0, 0.0),
+        camera_direction: Vec3::new(0.0, 0.0, 1.0),
+        shadow_map: None,
+        atmosphere: None,
+        lighting: settings.lighting,
+    };
 
-        std::thread::sleep(frame_delay);
+    render(
+        framebuffer,
+        &starship_uniforms,
+        &scene.starship_vertex_array,
+        scene.bodies.starship_shader.as_ref(),
+        // La malla de la nave no tiene un orden de vértices consistente
+        RenderState::double_sided(),
+    );
+
+    // Pasada translúcida, de atrás hacia adelante
+    transparent_draws.sort_by(|a, b| b.view_depth().total_cmp(&a.view_depth()));
+    for draw in &transparent_draws {
+        render(framebuffer, &draw.uniforms, draw.vertex_array, draw.shader, draw.state);
     }
+
+    // Promedia las muestras MSAA, agrega el bloom y lleva la radiancia HDR al buffer de 8 bits
+    framebuffer.resolve();
+    bloom::apply(&mut framebuffer.hdr_buffer, framebuffer_width, framebuffer_height, &settings.bloom);
+
+    // Destello de lente si el Sol se ve; con el z-buffer completo, los cuerpos delante lo tapan
+    let screen_transform = viewport_matrix * projection_matrix * view_matrix;
+    if let Some(sun) = flare::sun_on_screen(framebuffer, sun_translation, sun_scale, camera_direction, &screen_transform) {
+        flare::apply(&mut framebuffer.hdr_buffer, framebuffer_width, framebuffer_height, &sun, &settings.flare);
+    }
+
+    framebuffer.tone_map(settings.tone_mapping, settings.exposure);
 }
 
-fn render_rings(framebuffer: &mut Framebuffer, uniforms: &Uniforms, position: Vec3) {
-    let ring_inner_radius = 1.2;
-    let ring_outer_radius = 1.8;
-
-    for x in -400..400 {
-        for z in -400..400 {
-            let xf = x as f32 / 100.0;
-            let zf = z as f32 / 100.0;
-            let distance = (xf.powi(2) + zf.powi(2)).sqrt();
-
-            if distance > ring_inner_radius && distance < ring_outer_radius {
-                let rotated_x = xf;
-                let rotated_y = 0.0;
-                let rotated_z = zf;
-
-                let fragment = Fragment::new(
-                    Vec3::new(rotated_x, rotated_y, 0.0),
-                    Color::new(200, 200, 200),
-                    1.0,
-                    Vec3::new(0.0, 1.0, 0.0),
-                    1.0,
-                    position + Vec3::new(rotated_x, rotated_y, rotated_z),
-                );
-
-                let ring_color = ring_shader(&fragment, uniforms);
-
-                let x_screen = (rotated_x * 100.0 + framebuffer.width as f32 / 2.0) as usize;
-                let z_screen = (rotated_z * 100.0 + framebuffer.height as f32 / 2.0) as usize;
-
-                if x_screen < framebuffer.width && z_screen < framebuffer.height {
-                    framebuffer.set_current_color(ring_color.to_hex());
-                    framebuffer.point(x_screen, z_screen, 1.0);
+// Cada cuánto se revisa si cambiaron los archivos de configuración
+const RELOAD_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = match Options::from_args(&args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(2);
+        }
+    };
+
+    let mut scene = match Scene::load() {
+        Ok(scene) => scene,
+        Err(err) => {
+            eprintln!("Failed to load scene: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    if let Some(config) = &options.headless {
+        if let Err(err) = headless::run(config, &options.render, &scene) {
+            eprintln!("Headless render failed: {}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let window_width = 800;
+    let window_height = 600;
+    let framebuffer_width = 800;
+    let framebuffer_height = 600;
+    let frame_delay = Duration::from_millis(16);
+
+    let mut framebuffer = Framebuffer::with_samples(framebuffer_width, framebuffer_height, options.render.msaa_samples)
+        .expect("MSAA sample count is validated when parsing options");
+    let mut window = Window::new(
+        "Planetary System",
+        window_width,
+        window_height,
+        WindowOptions::default(),
+    )
+    .unwrap();
+
+    window.set_position(500, 500);
+    window.update();
+
+    //framebuffer.set_background_color(0x000000);
+    let mut camera = Camera::new();
+    let mut time = 0;
+
+    // Los archivos de configuración se revisan cada tanto; la cámara y el tiempo no cambian al recargar
+    let mut watcher = FileWatcher::new(&watched_paths());
+    let mut last_poll = Instant::now();
+
+    while window.is_open() && !window.is_key_down(Key::Escape) {
+        handle_camera_input(&mut camera, &window);
+        time += 1;
+
+        if last_poll.elapsed() >= RELOAD_POLL_INTERVAL {
+            last_poll = Instant::now();
+            if watcher.poll() {
+                match scene.reload() {
+                    Ok(()) => println!("Reloaded scene configuration"),
+                    Err(err) => eprintln!("Failed to reload scene: {}", err),
                 }
+                // `shaders.toml` puede nombrar otras texturas, aunque no haya cargado
+                watcher.watch(&watched_paths());
             }
         }
+
+        render_scene(&mut framebuffer, &scene, &camera, time, &options.render);
+
+        window
+            .update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height)
+            .unwrap();
+
+        std::thread::sleep(frame_delay);
     }
 }
 
-fn render_starship(
-    framebuffer: &mut Framebuffer,
-    starship_translation: Vec3,
-    starship_rotation: Vec3,
-    starship_scale: f32,
-    starship_vertex_array: &[Vertex],
-    view_matrix: Mat4,
-    time: u32,
-) {
-    let starship_model_matrix = create_model_matrix(starship_translation, starship_scale, starship_rotation);
-
-    let mut starship_uniforms = create_uniforms();
-    starship_uniforms.model_matrix = create_model_matrix(starship_translation, starship_scale, starship_rotation);
-    starship_uniforms.view_matrix = view_matrix;
-    starship_uniforms.time = time;    
+// Archivos de configuración y texturas que nombra `shaders.toml`
+fn watched_paths() -> Vec<PathBuf> {
+    let mut paths = vec![PathBuf::from(scene_config::SCENE_PATH), PathBuf::from(shader_config::SHADERS_PATH)];
+    paths.extend(shader_config::texture_paths(Path::new(shader_config::SHADERS_PATH)));
+    paths
 }
 
 fn handle_camera_input(camera: &mut Camera, window: &Window) {
@@ -0,0 +1,171 @@
+use std::f32::consts::PI;
+use nalgebra_glm::{Mat4, Vec3};
+use crate::color::{Color, HdrColor};
+use crate::fragment::Fragment;
+use crate::light::PointLight;
+use crate::shaders::normal_matrix;
+
+// Paso de las diferencias finitas del relieve, en unidades de espacio de modelo
+const BUMP_EPSILON: f32 = 5e-4;
+
+// Modelo de iluminación que usa la etapa de sombreado común
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightingModel {
+    // Lambert difuso más brillo especular de Blinn-Phong
+    BlinnPhong,
+    // Cook-Torrance con distribución GGX y Fresnel de Schlick
+    Pbr,
+}
+
+impl LightingModel {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "blinn-phong" => Some(LightingModel::BlinnPhong),
+            "pbr" => Some(LightingModel::Pbr),
+            _ => None,
+        }
+    }
+}
+
+/// Propiedades de superficie que produce un shader procedural; la iluminación se aplica después.
+#[derive(Debug, Clone, Copy)]
+pub struct Material {
+    // Color difuso; su alfa es la opacidad del fragmento
+    pub albedo: Color,
+    // 0 = pulido (brillo pequeño e intenso), 1 = mate
+    pub roughness: f32,
+    // Fuerza del brillo en Blinn-Phong, reflectancia a incidencia normal (F0) en PBR
+    pub specular: f32,
+    // Luz propia, que no depende de las luces de la escena
+    pub emissive: HdrColor,
+    // Luz ambiental ya multiplicada por su intensidad
+    pub ambient: Color,
+    // Normal de sombreado en espacio de mundo; `None` usa la interpolada del fragmento
+    pub normal: Option<Vec3>,
+}
+
+impl Material {
+    pub fn new(albedo: Color) -> Self {
+        Material {
+            albedo,
+            roughness: 1.0,
+            specular: 0.0,
+            emissive: HdrColor::new(0.0, 0.0, 0.0),
+            ambient: Color::black(),
+            normal: None,
+        }
+    }
+
+    pub fn with_roughness(self, roughness: f32) -> Self {
+        Material { roughness, ..self }
+    }
+
+    pub fn with_specular(self, specular: f32) -> Self {
+        Material { specular, ..self }
+    }
+
+    pub fn with_emissive(self, emissive: HdrColor) -> Self {
+        Material { emissive, ..self }
+    }
+
+    pub fn with_ambient(self, ambient: Color) -> Self {
+        Material { ambient, ..self }
+    }
+
+    pub fn with_normal(self, normal: Vec3) -> Self {
+        Material { normal: Some(normal), ..self }
+    }
+}
+
+/// Normal de sombreado con relieve: `height` da la altura de la superficie en un punto de espacio de modelo.
+/// La pendiente se estima con diferencias finitas y se devuelve la normal en espacio de mundo.
+pub fn bump_normal(fragment: &Fragment, model_matrix: &Mat4, height: impl Fn(&Vec3) -> f32) -> Vec3 {
+    let position = fragment.vertex_position;
+    let normal = fragment.normal;
+
+    let h = height(&position);
+    let gradient = Vec3::new(
+        height(&(position + Vec3::x() * BUMP_EPSILON)) - h,
+        height(&(position + Vec3::y() * BUMP_EPSILON)) - h,
+        height(&(position + Vec3::z() * BUMP_EPSILON)) - h,
+    ) / BUMP_EPSILON;
+
+    // Solo cuenta la pendiente a lo largo de la superficie
+    let surface_gradient = gradient - normal * normal.dot(&gradient);
+    let bumped = (normal - surface_gradient).normalize();
+
+    let world_normal = normal_matrix(model_matrix) * bumped;
+    // Conserva la orientación de la normal geométrica, por si se invirtió en una cara trasera
+    if world_normal.dot(&fragment.world_normal) < 0.0 {
+        -world_normal.normalize()
+    } else {
+        world_normal.normalize()
+    }
+}
+
+/// Etapa de iluminación común: combina el material con las luces, la sombra y la dirección de vista del fragmento.
+pub fn shade(material: &Material, fragment: &Fragment, lights: &[PointLight], model: LightingModel) -> HdrColor {
+    let albedo = HdrColor::from(material.albedo);
+    let normal = material.normal.unwrap_or(fragment.world_normal);
+    let view = fragment.view_dir;
+
+    let mut lit = HdrColor::black();
+    for light in lights {
+        let to_light = light.position - fragment.world_position;
+        let distance = to_light.magnitude();
+        if distance <= f32::EPSILON {
+            continue;
+        }
+        let light_dir = to_light / distance;
+        let n_dot_l = normal.dot(&light_dir);
+        if n_dot_l <= 0.0 {
+            continue;
+        }
+
+        let radiance = light.intensity * light.falloff.attenuation(distance) * fragment.visibility;
+        let half = (light_dir + view).normalize();
+        let n_dot_h = normal.dot(&half).max(0.0);
+
+        let reflected = match model {
+            LightingModel::BlinnPhong => {
+                let specular = material.specular * n_dot_h.powf(shininess(material.roughness));
+                albedo + HdrColor::new(specular, specular, specular)
+            }
+            LightingModel::Pbr => {
+                let n_dot_v = normal.dot(&view).max(1e-4);
+                let v_dot_h = view.dot(&half).max(0.0);
+                let fresnel = material.specular + (1.0 - material.specular) * (1.0 - v_dot_h).powi(5);
+                let specular = ggx_distribution(n_dot_h, material.roughness)
+                    * smith_geometry(n_dot_v, n_dot_l, material.roughness)
+                    * fresnel
+                    / (4.0 * n_dot_v * n_dot_l);
+                // Se multiplica por π para que un difuso blanco de frente a la luz valga 1, igual que en Blinn-Phong
+                albedo * (1.0 - fresnel) + HdrColor::new(specular, specular, specular) * PI
+            }
+        };
+
+        lit = lit + reflected * (n_dot_l * radiance);
+    }
+
+    let color = lit + HdrColor::from(material.ambient) + material.emissive;
+    color.with_alpha(albedo.a)
+}
+
+// Exponente de Blinn-Phong equivalente a una rugosidad dada
+fn shininess(roughness: f32) -> f32 {
+    let alpha = (roughness * roughness).max(1e-3);
+    (2.0 / (alpha * alpha) - 2.0).max(1.0)
+}
+
+fn ggx_distribution(n_dot_h: f32, roughness: f32) -> f32 {
+    let alpha = (roughness * roughness).max(1e-3);
+    let alpha2 = alpha * alpha;
+    let denominator = n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0;
+    alpha2 / (PI * denominator * denominator)
+}
+
+fn smith_geometry(n_dot_v: f32, n_dot_l: f32, roughness: f32) -> f32 {
+    let k = (roughness + 1.0).powi(2) / 8.0;
+    let schlick = |n_dot_x: f32| n_dot_x / (n_dot_x * (1.0 - k) + k);
+    schlick(n_dot_v) * schlick(n_dot_l)
+}
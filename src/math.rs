@@ -0,0 +1,5 @@
+/// Interpolación de Hermite entre 0 (con `x` <= `edge0`) y 1 (con `x` >= `edge1`), de pendiente nula en los extremos.
+pub fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
@@ -0,0 +1,188 @@
+use std::sync::Arc;
+use fastnoise_lite::{CellularDistanceFunction, CellularReturnType, DomainWarpType, FastNoiseLite, FractalType, NoiseType};
+
+/// Deformación del dominio: desplaza las coordenadas con otro ruido antes de muestrear.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DomainWarpConfig {
+    pub warp_type: DomainWarpType,
+    // Desplazamiento máximo, en las mismas unidades que las coordenadas que reciben los shaders
+    pub amplitude: f32,
+    pub frequency: f32,
+}
+
+/// Ajustes de un generador de ruido; los que no se indican quedan como en `FastNoiseLite`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoiseConfig {
+    pub seed: i32,
+    pub noise_type: NoiseType,
+    pub frequency: f32,
+    pub fractal_type: FractalType,
+    pub octaves: i32,
+    pub lacunarity: f32,
+    pub gain: f32,
+    pub cellular_distance: CellularDistanceFunction,
+    pub cellular_return: CellularReturnType,
+    pub domain_warp: Option<DomainWarpConfig>,
+}
+
+impl NoiseConfig {
+    pub fn new(noise_type: NoiseType) -> Self {
+        NoiseConfig {
+            seed: 1337,
+            noise_type,
+            frequency: 0.01,
+            fractal_type: FractalType::None,
+            octaves: 3,
+            lacunarity: 2.0,
+            gain: 0.5,
+            cellular_distance: CellularDistanceFunction::EuclideanSq,
+            cellular_return: CellularReturnType::Distance,
+            domain_warp: None,
+        }
+    }
+
+    /// Ruido suave que los shaders leen de `Uniforms::noise_open_simplex`.
+    pub fn open_simplex() -> Self {
+        NoiseConfig::new(NoiseType::OpenSimplex2)
+    }
+
+    /// Ruido de celdas que los shaders leen de `Uniforms::noise_cellular`.
+    pub fn cellular() -> Self {
+        NoiseConfig {
+            cellular_distance: CellularDistanceFunction::Manhattan,
+            ..NoiseConfig::new(NoiseType::Cellular)
+        }
+    }
+
+    pub fn build(&self) -> Noise {
+        let mut generator = FastNoiseLite::with_seed(self.seed);
+        generator.set_noise_type(Some(self.noise_type));
+        generator.set_frequency(Some(self.frequency));
+        generator.set_fractal_type(Some(self.fractal_type));
+        generator.set_fractal_octaves(Some(self.octaves));
+        generator.set_fractal_lacunarity(Some(self.lacunarity));
+        generator.set_fractal_gain(Some(self.gain));
+        generator.set_cellular_distance_function(Some(self.cellular_distance));
+        generator.set_cellular_return_type(Some(self.cellular_return));
+
+        let warp = self.domain_warp.map(|config| {
+            let mut warp = FastNoiseLite::with_seed(self.seed);
+            warp.set_domain_warp_type(Some(config.warp_type));
+            warp.set_domain_warp_amp(Some(config.amplitude));
+            warp.set_frequency(Some(config.frequency));
+            warp
+        });
+
+        Noise { generator, warp }
+    }
+}
+
+/// Generador de ruido ya configurado, con la deformación del dominio opcional incluida en cada muestra.
+pub struct Noise {
+    generator: FastNoiseLite,
+    warp: Option<FastNoiseLite>,
+}
+
+impl Noise {
+    pub fn get_noise_2d(&self, x: f32, y: f32) -> f32 {
+        let (x, y) = match &self.warp {
+            Some(warp) => warp.domain_warp_2d(x, y),
+            None => (x, y),
+        };
+        self.generator.get_noise_2d(x, y)
+    }
+
+    pub fn get_noise_3d(&self, x: f32, y: f32, z: f32) -> f32 {
+        let (x, y, z) = match &self.warp {
+            Some(warp) => warp.domain_warp_3d(x, y, z),
+            None => (x, y, z),
+        };
+        self.generator.get_noise_3d(x, y, z)
+    }
+}
+
+/// Ajustes de los dos generadores que recibe el shader de un cuerpo.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BodyNoiseConfig {
+    pub open_simplex: NoiseConfig,
+    pub cellular: NoiseConfig,
+}
+
+impl Default for BodyNoiseConfig {
+    fn default() -> Self {
+        BodyNoiseConfig {
+            open_simplex: NoiseConfig::open_simplex(),
+            cellular: NoiseConfig::cellular(),
+        }
+    }
+}
+
+impl BodyNoiseConfig {
+    pub fn build(&self) -> BodyNoise {
+        BodyNoise {
+            open_simplex: Arc::new(self.open_simplex.build()),
+            cellular: Arc::new(self.cellular.build()),
+        }
+    }
+}
+
+/// Generadores de un cuerpo, armados una vez al cargar la escena y compartidos con sus `Uniforms` en cada cuadro.
+#[derive(Clone)]
+pub struct BodyNoise {
+    pub open_simplex: Arc<Noise>,
+    pub cellular: Arc<Noise>,
+}
+
+pub fn noise_type_from_name(name: &str) -> Option<NoiseType> {
+    match name {
+        "open_simplex2" => Some(NoiseType::OpenSimplex2),
+        "open_simplex2s" => Some(NoiseType::OpenSimplex2S),
+        "cellular" => Some(NoiseType::Cellular),
+        "perlin" => Some(NoiseType::Perlin),
+        "value_cubic" => Some(NoiseType::ValueCubic),
+        "value" => Some(NoiseType::Value),
+        _ => None,
+    }
+}
+
+pub fn fractal_type_from_name(name: &str) -> Option<FractalType> {
+    match name {
+        "none" => Some(FractalType::None),
+        "fbm" => Some(FractalType::FBm),
+        "ridged" => Some(FractalType::Ridged),
+        "ping_pong" => Some(FractalType::PingPong),
+        _ => None,
+    }
+}
+
+pub fn cellular_distance_from_name(name: &str) -> Option<CellularDistanceFunction> {
+    match name {
+        "euclidean" => Some(CellularDistanceFunction::Euclidean),
+        "euclidean_sq" => Some(CellularDistanceFunction::EuclideanSq),
+        "manhattan" => Some(CellularDistanceFunction::Manhattan),
+        "hybrid" => Some(CellularDistanceFunction::Hybrid),
+        _ => None,
+    }
+}
+
+pub fn cellular_return_from_name(name: &str) -> Option<CellularReturnType> {
+    match name {
+        "cell_value" => Some(CellularReturnType::CellValue),
+        "distance" => Some(CellularReturnType::Distance),
+        "distance2" => Some(CellularReturnType::Distance2),
+        "distance2_add" => Some(CellularReturnType::Distance2Add),
+        "distance2_sub" => Some(CellularReturnType::Distance2Sub),
+        "distance2_mul" => Some(CellularReturnType::Distance2Mul),
+        "distance2_div" => Some(CellularReturnType::Distance2Div),
+        _ => None,
+    }
+}
+
+pub fn domain_warp_type_from_name(name: &str) -> Option<DomainWarpType> {
+    match name {
+        "open_simplex2" => Some(DomainWarpType::OpenSimplex2),
+        "open_simplex2_reduced" => Some(DomainWarpType::OpenSimplex2Reduced),
+        "basic_grid" => Some(DomainWarpType::BasicGrid),
+        _ => None,
+    }
+}
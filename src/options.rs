@@ -0,0 +1,176 @@
+use std::path::PathBuf;
+use crate::framebuffer::sample_pattern;
+use crate::headless::{HeadlessConfig, ImageFormat};
+use crate::tonemap::ToneMapping;
+use crate::bloom::BloomSettings;
+use crate::flare::LensFlareSettings;
+use crate::material::LightingModel;
+
+// Lado máximo del framebuffer; más grande ni siquiera cabe en memoria con varias muestras por píxel
+const MAX_RESOLUTION: usize = 16384;
+
+/// Ajustes de renderizado elegidos al iniciar, comunes al modo con ventana y al modo sin ventana.
+pub struct RenderSettings {
+    pub msaa_samples: usize,
+    pub tone_mapping: ToneMapping,
+    // Multiplicador de la radiancia antes del tone mapping
+    pub exposure: f32,
+    pub bloom: BloomSettings,
+    pub flare: LensFlareSettings,
+    pub lighting: LightingModel,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        RenderSettings {
+            msaa_samples: 1,
+            tone_mapping: ToneMapping::Clamp,
+            exposure: 1.0,
+            bloom: BloomSettings::default(),
+            flare: LensFlareSettings::default(),
+            lighting: LightingModel::BlinnPhong,
+        }
+    }
+}
+
+pub struct Options {
+    pub render: RenderSettings,
+    // `None` si no se pidió `--headless`
+    pub headless: Option<HeadlessConfig>,
+}
+
+impl Options {
+    /// Lee las opciones de la línea de comandos (sin el nombre del programa).
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        let mut render = RenderSettings::default();
+        let mut config = HeadlessConfig::default();
+        let mut headless = false;
+        let mut iter = args.iter();
+
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--msaa" => {
+                    render.msaa_samples = parse_value(arg, iter.next())?;
+                    if sample_pattern(render.msaa_samples).is_none() {
+                        return Err(format!("Unsupported MSAA sample count {} (expected 1, 2, 4 or 8)", render.msaa_samples));
+                    }
+                }
+                "--tonemap" => {
+                    let name = expect_value(arg, iter.next())?;
+                    render.tone_mapping = ToneMapping::from_name(name)
+                        .ok_or_else(|| format!("Unknown tone mapping '{}' (expected clamp, reinhard or aces)", name))?;
+                }
+                "--exposure" => {
+                    render.exposure = parse_value(arg, iter.next())?;
+                    if !render.exposure.is_finite() || render.exposure <= 0.0 {
+                        return Err(format!("Exposure must be a positive number, got {}", render.exposure));
+                    }
+                }
+                "--bloom-threshold" => {
+                    render.bloom.threshold = parse_value(arg, iter.next())?;
+                    if !render.bloom.threshold.is_finite() || render.bloom.threshold < 0.0 {
+                        return Err(format!("Bloom threshold must be zero or positive, got {}", render.bloom.threshold));
+                    }
+                }
+                "--bloom-intensity" => {
+                    render.bloom.intensity = parse_value(arg, iter.next())?;
+                    if !render.bloom.intensity.is_finite() || render.bloom.intensity < 0.0 {
+                        return Err(format!("Bloom intensity must be zero or positive, got {}", render.bloom.intensity));
+                    }
+                }
+                "--flare-intensity" => {
+                    render.flare.intensity = parse_value(arg, iter.next())?;
+                    if !render.flare.intensity.is_finite() || render.flare.intensity < 0.0 {
+                        return Err(format!("Flare intensity must be zero or positive, got {}", render.flare.intensity));
+                    }
+                }
+                "--lighting" => {
+                    let name = expect_value(arg, iter.next())?;
+                    render.lighting = LightingModel::from_name(name)
+                        .ok_or_else(|| format!("Unknown lighting model '{}' (expected blinn-phong or pbr)", name))?;
+                }
+                "--headless" => headless = true,
+                "--frames" => config.frames = parse_value(arg, iter.next())?,
+                "--start-time" => config.start_time = parse_value(arg, iter.next())?,
+                "--width" => config.width = parse_value(arg, iter.next())?,
+                "--height" => config.height = parse_value(arg, iter.next())?,
+                "--output" => config.output_dir = PathBuf::from(expect_value(arg, iter.next())?),
+                "--format" => {
+                    let name = expect_value(arg, iter.next())?;
+                    config.format = ImageFormat::from_name(name)
+                        .ok_or_else(|| format!("Unknown image format '{}' (expected png or ppm)", name))?;
+                }
+                other => return Err(format!("Unknown argument '{}'", other)),
+            }
+        }
+
+        if config.width == 0 || config.height == 0 {
+            return Err("Resolution must be at least 1x1".to_string());
+        }
+        if config.width > MAX_RESOLUTION || config.height > MAX_RESOLUTION {
+            return Err(format!(
+                "Resolution {}x{} is too large (each side must be at most {})",
+                config.width, config.height, MAX_RESOLUTION
+            ));
+        }
+
+        Ok(Options {
+            render,
+            headless: if headless { Some(config) } else { None },
+        })
+    }
+}
+
+fn expect_value<'a>(flag: &str, value: Option<&'a String>) -> Result<&'a str, String> {
+    value.map(|v| v.as_str()).ok_or_else(|| format!("Missing value for {}", flag))
+}
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<&String>) -> Result<T, String> {
+    let value = expect_value(flag, value)?;
+    value.parse().map_err(|_| format!("Invalid value '{}' for {}", value, flag))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::from_args(&args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn parses_headless_resolution() {
+        let options = parse(&["--headless", "--width", "1280", "--height", "720", "--msaa", "4"]).unwrap();
+        let config = options.headless.unwrap();
+
+        assert_eq!((config.width, config.height), (1280, 720));
+        assert_eq!(options.render.msaa_samples, 4);
+    }
+
+    #[test]
+    fn accepts_the_largest_resolution() {
+        let options = parse(&["--headless", "--width", "16384", "--height", "16384"]).unwrap();
+        let config = options.headless.unwrap();
+
+        assert_eq!((config.width, config.height), (MAX_RESOLUTION, MAX_RESOLUTION));
+    }
+
+    #[test]
+    fn rejects_out_of_range_resolutions() {
+        for (width, height) in [("0", "600"), ("800", "0"), ("16385", "600"), ("1000000", "1000000")] {
+            let result = parse(&["--headless", "--width", width, "--height", height]);
+            assert!(result.is_err(), "{}x{} should be rejected", width, height);
+        }
+
+        let error = parse(&["--width", "1000000"]).err().unwrap();
+        assert!(error.contains("at most 16384"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        assert_eq!(parse(&["--width", "wide"]).err().unwrap(), "Invalid value 'wide' for --width");
+        assert_eq!(parse(&["--height"]).err().unwrap(), "Missing value for --height");
+        assert!(parse(&["--msaa", "3"]).is_err());
+        assert_eq!(parse(&["--fast"]).err().unwrap(), "Unknown argument '--fast'");
+    }
+}
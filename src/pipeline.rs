@@ -0,0 +1,185 @@
+use nalgebra_glm::Vec3;
+use crate::color::HdrColor;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CullMode {
+    None,
+    Back,
+    Front,
+}
+
+// Orden de los vértices de una cara frontal, visto en pantalla (y hacia abajo)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrontFace {
+    Clockwise,
+    CounterClockwise,
+}
+
+// Cómo se combina el color del fragmento con el que ya está en el framebuffer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlendMode {
+    // Reemplaza el color de destino, ignorando el alfa
+    Opaque,
+    // src * alfa + dst * (1 - alfa)
+    Alpha,
+    // dst + src * alfa
+    Additive,
+}
+
+impl BlendMode {
+    pub fn apply(&self, src: &HdrColor, dst: &HdrColor) -> HdrColor {
+        match self {
+            BlendMode::Opaque => src.with_alpha(1.0),
+            BlendMode::Alpha => *src * src.a + *dst * (1.0 - src.a),
+            BlendMode::Additive => *dst + *src * src.a,
+        }
+    }
+}
+
+/// Estado fijo del pipeline que se elige por cada llamada a `render`.
+#[derive(Debug, Clone, Copy)]
+pub struct RenderState {
+    pub cull_mode: CullMode,
+    pub front_face: FrontFace,
+    // Prueba de profundidad antes del fragment shader; válida mientras los shaders no escriban profundidad
+    pub early_depth_test: bool,
+    pub blend: BlendMode,
+    pub depth_write: bool,
+    // Sin escritura de color no se ejecuta el fragment shader
+    pub color_write: bool,
+}
+
+impl RenderState {
+    // `Obj::load` invierte Y, por lo que las caras antihorarias del OBJ quedan horarias en pantalla
+    pub const fn opaque() -> Self {
+        RenderState {
+            cull_mode: CullMode::Back,
+            front_face: FrontFace::Clockwise,
+            early_depth_test: true,
+            blend: BlendMode::Opaque,
+            depth_write: true,
+            color_write: true,
+        }
+    }
+
+    pub const fn double_sided() -> Self {
+        RenderState {
+            cull_mode: CullMode::None,
+            ..RenderState::opaque()
+        }
+    }
+
+    // Geometría translúcida: se prueba contra la profundidad pero no la escribe
+    pub const fn transparent() -> Self {
+        RenderState {
+            cull_mode: CullMode::None,
+            blend: BlendMode::Alpha,
+            depth_write: false,
+            ..RenderState::opaque()
+        }
+    }
+
+    // Solo profundidad, para mapas de sombra; se dibujan ambas caras para no dejar huecos
+    pub const fn depth_only() -> Self {
+        RenderState {
+            cull_mode: CullMode::None,
+            color_write: false,
+            ..RenderState::opaque()
+        }
+    }
+
+    pub const fn with_blend(self, blend: BlendMode) -> Self {
+        RenderState { blend, ..self }
+    }
+
+    pub const fn with_cull_mode(self, cull_mode: CullMode) -> Self {
+        RenderState { cull_mode, ..self }
+    }
+
+    pub const fn with_front_face(self, front_face: FrontFace) -> Self {
+        RenderState { front_face, ..self }
+    }
+
+    pub const fn with_early_depth_test(self, early_depth_test: bool) -> Self {
+        RenderState { early_depth_test, ..self }
+    }
+
+    /// Indica si un triángulo ya proyectado a pantalla debe descartarse.
+    /// Los triángulos degenerados (área cero) siempre se descartan.
+    pub fn is_culled(&self, a: &Vec3, b: &Vec3, c: &Vec3) -> bool {
+        let signed_area = (c.x - a.x) * (b.y - a.y) - (c.y - a.y) * (b.x - a.x);
+        if signed_area == 0.0 {
+            return true;
+        }
+
+        // Con y hacia abajo, un área negativa es un recorrido horario en pantalla
+        let clockwise = signed_area < 0.0;
+        let is_front = match self.front_face {
+            FrontFace::Clockwise => clockwise,
+            FrontFace::CounterClockwise => !clockwise,
+        };
+
+        match self.cull_mode {
+            CullMode::None => false,
+            CullMode::Back => !is_front,
+            CullMode::Front => is_front,
+        }
+    }
+}
+
+impl Default for RenderState {
+    fn default() -> Self {
+        RenderState::opaque()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Con y hacia abajo: derecha y luego abajo es un recorrido horario en pantalla
+    const CLOCKWISE: [Vec3; 3] = [Vec3::new(0.0, 0.0, 0.0), Vec3::new(10.0, 0.0, 0.0), Vec3::new(0.0, 10.0, 0.0)];
+    const COUNTER_CLOCKWISE: [Vec3; 3] = [Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 10.0, 0.0), Vec3::new(10.0, 0.0, 0.0)];
+
+    fn culled(cull_mode: CullMode, front_face: FrontFace, [a, b, c]: [Vec3; 3]) -> bool {
+        RenderState::opaque()
+            .with_cull_mode(cull_mode)
+            .with_front_face(front_face)
+            .is_culled(&a, &b, &c)
+    }
+
+    #[test]
+    fn culls_by_mode_and_front_face() {
+        // (modo, cara frontal, ¿se descarta el horario?, ¿se descarta el antihorario?)
+        let cases = [
+            (CullMode::None, FrontFace::Clockwise, false, false),
+            (CullMode::None, FrontFace::CounterClockwise, false, false),
+            (CullMode::Back, FrontFace::Clockwise, false, true),
+            (CullMode::Back, FrontFace::CounterClockwise, true, false),
+            (CullMode::Front, FrontFace::Clockwise, true, false),
+            (CullMode::Front, FrontFace::CounterClockwise, false, true),
+        ];
+
+        for (cull_mode, front_face, clockwise, counter_clockwise) in cases {
+            assert_eq!(culled(cull_mode, front_face, CLOCKWISE), clockwise, "{:?} {:?} clockwise", cull_mode, front_face);
+            assert_eq!(
+                culled(cull_mode, front_face, COUNTER_CLOCKWISE),
+                counter_clockwise,
+                "{:?} {:?} counter-clockwise",
+                cull_mode,
+                front_face
+            );
+        }
+    }
+
+    #[test]
+    fn always_culls_degenerate_triangles() {
+        let collinear = [Vec3::new(0.0, 0.0, 0.0), Vec3::new(5.0, 5.0, 0.0), Vec3::new(10.0, 10.0, 0.0)];
+
+        for cull_mode in [CullMode::None, CullMode::Back, CullMode::Front] {
+            for front_face in [FrontFace::Clockwise, FrontFace::CounterClockwise] {
+                assert!(culled(cull_mode, front_face, collinear));
+            }
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use rayon::prelude::*;
+use crate::color::HdrColor;
+use crate::framebuffer::{Framebuffer, TILE_SIZE};
+use crate::pipeline::{CullMode, RenderState};
+use crate::shader_registry::FragmentShader;
+use crate::triangle::{triangle, calculate_bounding_box};
+use crate::vertex::Vertex;
+use crate::Uniforms;
+
+/// Rasteriza y sombrea triángulos ya proyectados a pantalla. Cada triángulo se asigna a los
+/// tiles que toca su caja envolvente y los tiles se procesan en paralelo.
+pub fn rasterize(
+    framebuffer: &mut Framebuffer,
+    triangles: &[[Vertex; 3]],
+    uniforms: &Uniforms,
+    shader: &dyn FragmentShader,
+    state: RenderState,
+) {
+    let (tiles_x, tiles_y) = framebuffer.tile_counts();
+    let bins = bin_triangles(triangles, framebuffer.width, framebuffer.height, tiles_x, tiles_y);
+    let mut tiles = framebuffer.tiles_mut();
+    let texture_derivatives = state.color_write && shader.uses_texture_derivatives();
+
+    tiles.par_iter_mut()
+        .zip(bins.par_iter())
+        .filter(|(_, bin)| !bin.is_empty())
+        .for_each(|(tile, bin)| {
+            let clip_rect = (
+                tile.x as i32,
+                tile.y as i32,
+                (tile.x + tile.width) as i32 - 1,
+                (tile.y + tile.height) as i32 - 1,
+            );
+
+            for &index in bin {
+                let [v1, v2, v3] = &triangles[index];
+                for mut fragment in triangle(v1, v2, v3, clip_rect, tile.sample_pattern, texture_derivatives) {
+                    // Early-Z: los fragmentos ocultos no pasan por el fragment shader
+                    if state.early_depth_test && tile.depth_test(&fragment) == 0 {
+                        continue;
+                    }
+
+                    // Pasada solo de profundidad (p. ej. mapas de sombra): no hace falta sombrear
+                    if !state.color_write {
+                        tile.write_fragment(&fragment, &HdrColor::black(), &state);
+                        continue;
+                    }
+
+                    // Vista y sombras por fragmento, en espacio de mundo
+                    fragment.view_dir = uniforms.view_direction(&fragment.world_position);
+                    // Sin culling se ven ambas caras: la normal se orienta hacia la cámara
+                    if state.cull_mode == CullMode::None && fragment.world_normal.dot(&fragment.view_dir) < 0.0 {
+                        fragment.world_normal = -fragment.world_normal;
+                    }
+                    if let Some(shadow_map) = &uniforms.shadow_map {
+                        fragment.visibility = shadow_map.visibility(&fragment.world_position, &fragment.world_normal);
+                    }
+
+                    let shaded_color = shader.shade(&fragment, uniforms);
+                    tile.write_fragment(&fragment, &shaded_color, &state);
+                }
+            }
+        });
+}
+
+// Lista, por tile, de los índices de triángulos cuya caja envolvente lo toca (en orden de dibujo)
+fn bin_triangles(triangles: &[[Vertex; 3]], width: usize, height: usize, tiles_x: usize, tiles_y: usize) -> Vec<Vec<usize>> {
+    let mut bins = vec![Vec::new(); tiles_x * tiles_y];
+
+    for (index, [v1, v2, v3]) in triangles.iter().enumerate() {
+        let (min_x, min_y, max_x, max_y) = calculate_bounding_box(
+            &v1.transformed_position,
+            &v2.transformed_position,
+            &v3.transformed_position,
+        );
+        if max_x < 0 || max_y < 0 || min_x >= width as i32 || min_y >= height as i32 {
+            continue;
+        }
+
+        let first_x = min_x.max(0) as usize / TILE_SIZE;
+        let first_y = min_y.max(0) as usize / TILE_SIZE;
+        let last_x = (max_x as usize).min(width - 1) / TILE_SIZE;
+        let last_y = (max_y as usize).min(height - 1) / TILE_SIZE;
+
+        for tile_y in first_y..=last_y {
+            for tile_x in first_x..=last_x {
+                bins[tile_y * tiles_x + tile_x].push(index);
+            }
+        }
+    }
+
+    bins
+}
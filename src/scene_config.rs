@@ -0,0 +1,482 @@
+use std::fs;
+use std::path::Path;
+use serde::Deserialize;
+use crate::atmosphere::Atmosphere;
+use crate::light::Falloff;
+use crate::noise::{self, BodyNoiseConfig, DomainWarpConfig, NoiseConfig};
+use crate::shader_config::ColorDefinition;
+
+/// Archivo con los planetas del sistema y el ruido que usan sus shaders.
+pub const SCENE_PATH: &str = "assets/scene.toml";
+
+/// Planeta tal como se describe en el archivo de escena; el shader se busca por nombre en el registro.
+#[derive(Debug, Clone)]
+pub struct PlanetConfig {
+    pub name: String,
+    pub shader: String,
+    pub scale: f32,
+    pub orbit_radius: f32,
+    pub orbit_speed: f32,
+    pub rotation_speed: f32,
+    pub atmosphere: Option<Atmosphere>,
+    // Ruido propio del planeta
+    pub noise: BodyNoiseConfig,
+    // Anillos inclinados alrededor del planeta
+    pub rings: bool,
+    // Ruido propio de los anillos; sin él usan el del planeta
+    pub rings_noise: Option<BodyNoiseConfig>,
+    // Luna que orbita al planeta
+    pub moon: bool,
+    // Ruido propio de la luna; sin él usa el del planeta
+    pub moon_noise: Option<BodyNoiseConfig>,
+}
+
+/// Contenido del archivo de escena, ya validado.
+#[derive(Debug, Clone)]
+pub struct SceneConfig {
+    // Ruido del fondo y punto de partida del de cada cuerpo
+    pub noise: BodyNoiseConfig,
+    // Ruido propio del Sol y de la nave; sin él usan el de la escena
+    pub sun_noise: Option<BodyNoiseConfig>,
+    pub starship_noise: Option<BodyNoiseConfig>,
+    // Cómo decae la luz del Sol con la distancia
+    pub sun_falloff: Falloff,
+    pub planets: Vec<PlanetConfig>,
+}
+
+// Formato del archivo: tablas `[sun]`, `[noise]`, `[sun_noise]` y `[starship_noise]` opcionales
+// y una tabla `[[planet]]` por planeta, de adentro hacia afuera
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneFile {
+    #[serde(default)]
+    sun: SunDefinition,
+    #[serde(default)]
+    noise: BodyNoiseDefinition,
+    sun_noise: Option<BodyNoiseDefinition>,
+    starship_noise: Option<BodyNoiseDefinition>,
+    #[serde(default, rename = "planet")]
+    planets: Vec<PlanetDefinition>,
+}
+
+// Los dos generadores de un cuerpo; los campos que faltan se toman del cuerpo del que dependen
+// (la luna y los anillos de su planeta) o de la tabla `[noise]` de la escena
+#[derive(Deserialize, Default, Clone)]
+#[serde(deny_unknown_fields)]
+struct BodyNoiseDefinition {
+    #[serde(default)]
+    open_simplex: NoiseDefinition,
+    #[serde(default)]
+    cellular: NoiseDefinition,
+}
+
+#[derive(Deserialize, Default, Clone)]
+#[serde(deny_unknown_fields)]
+struct NoiseDefinition {
+    seed: Option<i32>,
+    noise_type: Option<String>,
+    frequency: Option<f32>,
+    fractal_type: Option<String>,
+    octaves: Option<i32>,
+    lacunarity: Option<f32>,
+    gain: Option<f32>,
+    cellular_distance: Option<String>,
+    cellular_return: Option<String>,
+    domain_warp: Option<DomainWarpDefinition>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+struct DomainWarpDefinition {
+    #[serde(default = "default_warp_type", rename = "type")]
+    warp_type: String,
+    amplitude: f32,
+    // Por defecto, la misma frecuencia que el ruido que deforma
+    frequency: Option<f32>,
+}
+
+fn default_warp_type() -> String {
+    "open_simplex2".to_string()
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct SunDefinition {
+    #[serde(default)]
+    falloff: FalloffDefinition,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum FalloffDefinition {
+    // Variante con llaves para que `deny_unknown_fields` también la alcance
+    None {},
+    Linear { range: f32 },
+    InverseSquare { radius: f32 },
+}
+
+// La luz del Sol llega a la mitad de su intensidad a 800 unidades
+impl Default for FalloffDefinition {
+    fn default() -> Self {
+        FalloffDefinition::InverseSquare { radius: 800.0 }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PlanetDefinition {
+    name: String,
+    shader: String,
+    scale: f32,
+    orbit_radius: f32,
+    orbit_speed: f32,
+    #[serde(default)]
+    rotation_speed: f32,
+    atmosphere: Option<AtmosphereDefinition>,
+    #[serde(default)]
+    noise: BodyNoiseDefinition,
+    #[serde(default)]
+    rings: bool,
+    rings_noise: Option<BodyNoiseDefinition>,
+    #[serde(default)]
+    moon: bool,
+    moon_noise: Option<BodyNoiseDefinition>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AtmosphereDefinition {
+    height: f32,
+    rayleigh: f32,
+    mie: f32,
+    mie_g: f32,
+    color: ColorDefinition,
+}
+
+/// Lee y valida el archivo de escena en `path`.
+pub fn load_scene_config(path: &Path) -> Result<SceneConfig, String> {
+    let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+    parse_scene_config(&text).map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+/// Interpreta el texto TOML de un archivo de escena.
+pub fn parse_scene_config(text: &str) -> Result<SceneConfig, String> {
+    let file: SceneFile = toml::from_str(text).map_err(|err| err.to_string())?;
+
+    let sun_falloff = file.sun.falloff.build().map_err(|err| format!("sun: {}", err))?;
+    let noise = file.noise.build().map_err(|err| format!("noise: {}", err))?;
+    let sun_noise = file.sun_noise
+        .as_ref()
+        .map(|definition| definition.or(&file.noise).build().map_err(|err| format!("sun_noise: {}", err)))
+        .transpose()?;
+    let starship_noise = file.starship_noise
+        .as_ref()
+        .map(|definition| definition.or(&file.noise).build().map_err(|err| format!("starship_noise: {}", err)))
+        .transpose()?;
+    let planets = file.planets
+        .iter()
+        .map(|definition| {
+            definition.build(&file.noise).map_err(|err| format!("planet '{}': {}", definition.name, err))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(SceneConfig { noise, sun_noise, starship_noise, sun_falloff, planets })
+}
+
+impl BodyNoiseDefinition {
+    // Completa los campos que faltan con los de `fallback`
+    fn or(&self, fallback: &BodyNoiseDefinition) -> BodyNoiseDefinition {
+        BodyNoiseDefinition {
+            open_simplex: self.open_simplex.or(&fallback.open_simplex),
+            cellular: self.cellular.or(&fallback.cellular),
+        }
+    }
+
+    fn build(&self) -> Result<BodyNoiseConfig, String> {
+        Ok(BodyNoiseConfig {
+            open_simplex: self.open_simplex.build(NoiseConfig::open_simplex()).map_err(|err| format!("open_simplex: {}", err))?,
+            cellular: self.cellular.build(NoiseConfig::cellular()).map_err(|err| format!("cellular: {}", err))?,
+        })
+    }
+}
+
+impl NoiseDefinition {
+    fn or(&self, fallback: &NoiseDefinition) -> NoiseDefinition {
+        NoiseDefinition {
+            seed: self.seed.or(fallback.seed),
+            noise_type: self.noise_type.clone().or_else(|| fallback.noise_type.clone()),
+            frequency: self.frequency.or(fallback.frequency),
+            fractal_type: self.fractal_type.clone().or_else(|| fallback.fractal_type.clone()),
+            octaves: self.octaves.or(fallback.octaves),
+            lacunarity: self.lacunarity.or(fallback.lacunarity),
+            gain: self.gain.or(fallback.gain),
+            cellular_distance: self.cellular_distance.clone().or_else(|| fallback.cellular_distance.clone()),
+            cellular_return: self.cellular_return.clone().or_else(|| fallback.cellular_return.clone()),
+            domain_warp: self.domain_warp.clone().or_else(|| fallback.domain_warp.clone()),
+        }
+    }
+
+    // Aplica los campos indicados sobre `defaults`
+    fn build(&self, defaults: NoiseConfig) -> Result<NoiseConfig, String> {
+        let mut config = defaults;
+        if let Some(seed) = self.seed {
+            config.seed = seed;
+        }
+        if let Some(name) = &self.noise_type {
+            config.noise_type = noise::noise_type_from_name(name).ok_or_else(|| {
+                format!("Unknown noise type '{}' (expected open_simplex2, open_simplex2s, cellular, perlin, value_cubic or value)", name)
+            })?;
+        }
+        if let Some(frequency) = self.frequency {
+            if frequency <= 0.0 {
+                return Err(format!("frequency must be positive, got {}", frequency));
+            }
+            config.frequency = frequency;
+        }
+        if let Some(name) = &self.fractal_type {
+            config.fractal_type = noise::fractal_type_from_name(name)
+                .ok_or_else(|| format!("Unknown fractal type '{}' (expected none, fbm, ridged or ping_pong)", name))?;
+        }
+        if let Some(octaves) = self.octaves {
+            if octaves < 1 {
+                return Err(format!("octaves must be at least 1, got {}", octaves));
+            }
+            config.octaves = octaves;
+        }
+        if let Some(lacunarity) = self.lacunarity {
+            config.lacunarity = lacunarity;
+        }
+        if let Some(gain) = self.gain {
+            config.gain = gain;
+        }
+        if let Some(name) = &self.cellular_distance {
+            config.cellular_distance = noise::cellular_distance_from_name(name).ok_or_else(|| {
+                format!("Unknown cellular distance '{}' (expected euclidean, euclidean_sq, manhattan or hybrid)", name)
+            })?;
+        }
+        if let Some(name) = &self.cellular_return {
+            config.cellular_return = noise::cellular_return_from_name(name).ok_or_else(|| {
+                format!(
+                    "Unknown cellular return type '{}' (expected cell_value, distance, distance2, distance2_add, distance2_sub, distance2_mul or distance2_div)",
+                    name
+                )
+            })?;
+        }
+        if let Some(warp) = &self.domain_warp {
+            config.domain_warp = Some(warp.build(config.frequency)?);
+        }
+        Ok(config)
+    }
+}
+
+impl DomainWarpDefinition {
+    fn build(&self, noise_frequency: f32) -> Result<DomainWarpConfig, String> {
+        let warp_type = noise::domain_warp_type_from_name(&self.warp_type).ok_or_else(|| {
+            format!("Unknown domain warp type '{}' (expected open_simplex2, open_simplex2_reduced or basic_grid)", self.warp_type)
+        })?;
+        let frequency = self.frequency.unwrap_or(noise_frequency);
+        if frequency <= 0.0 {
+            return Err(format!("domain warp frequency must be positive, got {}", frequency));
+        }
+
+        Ok(DomainWarpConfig { warp_type, amplitude: self.amplitude, frequency })
+    }
+}
+
+impl FalloffDefinition {
+    fn build(&self) -> Result<Falloff, String> {
+        Ok(match *self {
+            FalloffDefinition::None {} => Falloff::None,
+            FalloffDefinition::Linear { range } => {
+                if range <= 0.0 {
+                    return Err(format!("falloff range must be positive, got {}", range));
+                }
+                Falloff::Linear { range }
+            }
+            FalloffDefinition::InverseSquare { radius } => {
+                if radius <= 0.0 {
+                    return Err(format!("falloff radius must be positive, got {}", radius));
+                }
+                Falloff::InverseSquare { radius }
+            }
+        })
+    }
+}
+
+impl PlanetDefinition {
+    fn build(&self, scene_noise: &BodyNoiseDefinition) -> Result<PlanetConfig, String> {
+        if self.scale <= 0.0 {
+            return Err(format!("scale must be positive, got {}", self.scale));
+        }
+        if self.orbit_radius < 0.0 {
+            return Err(format!("orbit_radius must be zero or positive, got {}", self.orbit_radius));
+        }
+        if self.rings_noise.is_some() && !self.rings {
+            return Err("rings_noise requires rings = true".to_string());
+        }
+        if self.moon_noise.is_some() && !self.moon {
+            return Err("moon_noise requires moon = true".to_string());
+        }
+
+        let noise = self.noise.or(scene_noise);
+        // La luna y los anillos parten del ruido del planeta
+        let companion_noise = |definition: &Option<BodyNoiseDefinition>, field: &str| {
+            definition
+                .as_ref()
+                .map(|definition| definition.or(&noise).build().map_err(|err| format!("{}: {}", field, err)))
+                .transpose()
+        };
+
+        Ok(PlanetConfig {
+            name: self.name.clone(),
+            shader: self.shader.clone(),
+            scale: self.scale,
+            orbit_radius: self.orbit_radius,
+            orbit_speed: self.orbit_speed,
+            rotation_speed: self.rotation_speed,
+            atmosphere: self.atmosphere.as_ref().map(AtmosphereDefinition::build).transpose()?,
+            noise: noise.build().map_err(|err| format!("noise: {}", err))?,
+            rings: self.rings,
+            rings_noise: companion_noise(&self.rings_noise, "rings_noise")?,
+            moon: self.moon,
+            moon_noise: companion_noise(&self.moon_noise, "moon_noise")?,
+        })
+    }
+}
+
+impl AtmosphereDefinition {
+    fn build(&self) -> Result<Atmosphere, String> {
+        if self.height <= 0.0 {
+            return Err(format!("atmosphere height must be positive, got {}", self.height));
+        }
+        if !(-1.0..1.0).contains(&self.mie_g) {
+            return Err(format!("atmosphere mie_g must be between -1 and 1, got {}", self.mie_g));
+        }
+
+        Ok(Atmosphere::new(self.height, self.rayleigh, self.mie, self.mie_g, self.color.to_color()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Planeta mínimo al que cada prueba le agrega líneas
+    fn planet(extra: &str) -> String {
+        format!(
+            "[[planet]]\nname = \"P\"\nshader = \"rocky_planet_shader\"\nscale = 1.0\norbit_radius = 10.0\norbit_speed = 0.1\n{}\n",
+            extra
+        )
+    }
+
+    fn error_of(text: &str) -> String {
+        parse_scene_config(text).expect_err("the scene should be rejected")
+    }
+
+    #[test]
+    fn bundled_scene_is_valid() {
+        let config = load_scene_config(Path::new(SCENE_PATH)).unwrap();
+        assert!(!config.planets.is_empty());
+    }
+
+    #[test]
+    fn empty_file_uses_defaults() {
+        let config = parse_scene_config("").unwrap();
+        assert_eq!(config.noise, BodyNoiseConfig::default());
+        assert!(config.sun_noise.is_none());
+        assert!(config.starship_noise.is_none());
+        assert_eq!(config.sun_falloff, Falloff::InverseSquare { radius: 800.0 });
+        assert!(config.planets.is_empty());
+    }
+
+    #[test]
+    fn planet_noise_falls_back_to_scene_noise() {
+        let text = format!(
+            "[noise.open_simplex]\nseed = 5\nfractal_type = \"fbm\"\n\n{}",
+            planet("noise = { open_simplex = { frequency = 0.02 } }")
+        );
+        let config = parse_scene_config(&text).unwrap();
+        let noise = &config.planets[0].noise;
+
+        assert_eq!(noise.open_simplex.seed, 5);
+        assert_eq!(noise.open_simplex.fractal_type, fastnoise_lite::FractalType::FBm);
+        assert_eq!(noise.open_simplex.frequency, 0.02);
+        assert_eq!(noise.cellular, NoiseConfig::cellular());
+    }
+
+    #[test]
+    fn companion_noise_falls_back_to_planet_noise() {
+        let text = planet("noise = { open_simplex = { seed = 7 } }\nmoon = true\nmoon_noise = { cellular = { seed = 9 } }");
+        let config = parse_scene_config(&text).unwrap();
+        let moon_noise = config.planets[0].moon_noise.unwrap();
+
+        assert_eq!(moon_noise.open_simplex.seed, 7);
+        assert_eq!(moon_noise.cellular.seed, 9);
+        assert!(config.planets[0].rings_noise.is_none());
+    }
+
+    #[test]
+    fn sun_noise_falls_back_to_scene_noise() {
+        let config = parse_scene_config("[noise.cellular]\nseed = 3\n\n[sun_noise.open_simplex]\nseed = 4\n").unwrap();
+        let sun_noise = config.sun_noise.unwrap();
+
+        assert_eq!(sun_noise.open_simplex.seed, 4);
+        assert_eq!(sun_noise.cellular.seed, 3);
+    }
+
+    #[test]
+    fn sun_falloff() {
+        let falloff = |text: &str| parse_scene_config(text).map(|config| config.sun_falloff);
+        assert_eq!(falloff("[sun]\nfalloff = { type = \"none\" }\n"), Ok(Falloff::None));
+        assert_eq!(falloff("[sun]\nfalloff = { type = \"linear\", range = 900.0 }\n"), Ok(Falloff::Linear { range: 900.0 }));
+        assert_eq!(
+            falloff("[sun]\nfalloff = { type = \"inverse_square\", radius = 400.0 }\n"),
+            Ok(Falloff::InverseSquare { radius: 400.0 })
+        );
+        assert!(error_of("[sun]\nfalloff = { type = \"linear\", range = 0.0 }\n").contains("sun: falloff range must be positive"));
+        assert!(error_of("[sun]\nfalloff = { type = \"inverse_square\", radius = -1.0 }\n").contains("falloff radius must be positive"));
+        assert!(error_of("[sun]\nfalloff = { type = \"exponential\" }\n").contains("unknown variant"));
+        assert!(error_of("[sun]\nfalloff = { type = \"none\", range = 1.0 }\n").contains("unknown field"));
+    }
+
+    #[test]
+    fn rejects_malformed_toml() {
+        assert!(parse_scene_config("[[planet]\nname = ").is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        assert!(error_of(&planet("colour = 3")).contains("unknown field"));
+    }
+
+    #[test]
+    fn rejects_missing_fields() {
+        assert!(error_of("[[planet]]\nname = \"P\"\n").contains("missing field"));
+    }
+
+    #[test]
+    fn rejects_invalid_planet_values() {
+        assert!(error_of(&planet("").replace("scale = 1.0", "scale = -1.0")).contains("planet 'P': scale must be positive"));
+        assert!(error_of(&planet("").replace("orbit_radius = 10.0", "orbit_radius = -1.0")).contains("orbit_radius"));
+        assert!(error_of(&planet("moon_noise = { open_simplex = { seed = 1 } }")).contains("moon_noise requires moon = true"));
+        assert!(error_of(&planet("rings_noise = {}")).contains("rings_noise requires rings = true"));
+    }
+
+    #[test]
+    fn rejects_invalid_noise_values() {
+        assert!(error_of("[noise.open_simplex]\nnoise_type = \"plasma\"\n").contains("noise: open_simplex: Unknown noise type 'plasma'"));
+        assert!(error_of("[noise.cellular]\nfrequency = 0.0\n").contains("frequency must be positive"));
+        assert!(error_of("[noise.cellular]\noctaves = 0\n").contains("octaves must be at least 1"));
+        assert!(error_of("[starship_noise.cellular]\ncellular_return = \"far\"\n").contains("starship_noise: cellular: Unknown cellular return type"));
+        assert!(error_of(&planet("noise = { open_simplex = { domain_warp = { type = \"swirl\", amplitude = 1.0 } } }"))
+            .contains("Unknown domain warp type 'swirl'"));
+    }
+
+    #[test]
+    fn rejects_invalid_atmosphere() {
+        let atmosphere = "atmosphere = { height = 0.2, rayleigh = 1.0, mie = 1.0, mie_g = 1.5, color = [0, 0, 0] }";
+        assert!(error_of(&planet(atmosphere)).contains("mie_g must be between -1 and 1"));
+        let atmosphere = "atmosphere = { height = 0.0, rayleigh = 1.0, mie = 1.0, mie_g = 0.5, color = [0, 0, 0] }";
+        assert!(error_of(&planet(atmosphere)).contains("height must be positive"));
+    }
+}
@@ -0,0 +1,495 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use nalgebra_glm::Vec3;
+use serde::Deserialize;
+use crate::color::{Color, ColorSpace, Gradient, Interpolation};
+use crate::layers::{Layer, LayerBlend, LayerStack, NoiseSource, Pattern};
+use crate::shader_registry::ShaderRegistry;
+use crate::texture::{Filter, Sampler, Texture, WrapMode};
+
+/// Archivo de definiciones de shaders que se carga al iniciar.
+pub const SHADERS_PATH: &str = "assets/shaders.toml";
+
+// Formato del archivo: una tabla `[[shader]]` por estilo, con sus `[[shader.layer]]` en orden
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ShaderFile {
+    #[serde(default, rename = "shader")]
+    shaders: Vec<ShaderDefinition>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ShaderDefinition {
+    name: String,
+    // Con textura, tiñe la imagen y por defecto es blanco
+    base: Option<ColorDefinition>,
+    texture: Option<TextureDefinition>,
+    ambient: Option<ColorDefinition>,
+    #[serde(default)]
+    specular: f32,
+    #[serde(default = "default_roughness")]
+    roughness: f32,
+    #[serde(default, rename = "layer")]
+    layers: Vec<LayerDefinition>,
+}
+
+// Imagen que se lee con las coordenadas de textura de la malla; la ruta es relativa al directorio de trabajo
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TextureDefinition {
+    path: String,
+    #[serde(default = "default_filter")]
+    filter: String,
+    #[serde(default = "default_wrap")]
+    wrap_u: String,
+    #[serde(default = "default_wrap")]
+    wrap_v: String,
+    #[serde(default = "default_mipmaps")]
+    mipmaps: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LayerDefinition {
+    pattern: PatternDefinition,
+    // Rampa de la capa: `colors` reparte los colores a la par entre 0 y 1; `gradient` da las paradas y el modo
+    colors: Option<Vec<ColorDefinition>>,
+    gradient: Option<GradientDefinition>,
+    #[serde(default = "default_blend")]
+    blend: String,
+    #[serde(default = "default_opacity")]
+    opacity: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum PatternDefinition {
+    Noise {
+        #[serde(default = "default_noise")]
+        noise: String,
+        zoom: f32,
+        #[serde(default = "default_octaves")]
+        octaves: u32,
+        #[serde(default)]
+        speed: f32,
+    },
+    Bands {
+        count: f32,
+        #[serde(default = "default_axis")]
+        axis: [f32; 3],
+        #[serde(default)]
+        turbulence: f32,
+        #[serde(default)]
+        speed: f32,
+    },
+    Cells {
+        zoom: f32,
+        #[serde(default)]
+        speed: f32,
+    },
+    Gradient {
+        #[serde(default = "default_axis")]
+        axis: [f32; 3],
+    },
+}
+
+/// Rampa de colores en un archivo de configuración.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GradientDefinition {
+    stops: Vec<StopDefinition>,
+    #[serde(default = "default_interpolation")]
+    interpolation: String,
+    #[serde(default = "default_space")]
+    space: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StopDefinition {
+    at: f32,
+    color: ColorDefinition,
+}
+
+/// Color en un archivo de configuración: `[r, g, b]` o una cadena `"#rrggbb"`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum ColorDefinition {
+    Rgb([u8; 3]),
+    Hex(String),
+}
+
+fn default_roughness() -> f32 {
+    1.0
+}
+
+fn default_filter() -> String {
+    "bilinear".to_string()
+}
+
+fn default_wrap() -> String {
+    "repeat".to_string()
+}
+
+fn default_mipmaps() -> bool {
+    true
+}
+
+fn default_blend() -> String {
+    "normal".to_string()
+}
+
+fn default_opacity() -> f32 {
+    1.0
+}
+
+fn default_interpolation() -> String {
+    "linear".to_string()
+}
+
+fn default_space() -> String {
+    "rgb".to_string()
+}
+
+fn default_noise() -> String {
+    "open_simplex".to_string()
+}
+
+fn default_octaves() -> u32 {
+    1
+}
+
+fn default_axis() -> [f32; 3] {
+    [0.0, 1.0, 0.0]
+}
+
+/// Lee las definiciones de `path` y las registra como `LayerStack`; una definición con el nombre
+/// de un shader existente lo reemplaza.
+pub fn load_shader_definitions(path: &Path, registry: &mut ShaderRegistry) -> Result<(), String> {
+    let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+    for (name, shader) in parse_shader_definitions(&text).map_err(|err| format!("{}: {}", path.display(), err))? {
+        registry.register(&name, shader);
+    }
+    Ok(())
+}
+
+/// Rutas de las texturas que nombra el archivo de definiciones, aunque alguna no se pueda cargar,
+/// para vigilarlas junto con el archivo. Si el archivo no se puede leer o interpretar, no hay rutas.
+pub fn texture_paths(path: &Path) -> Vec<PathBuf> {
+    let Some(file) = fs::read_to_string(path).ok().and_then(|text| toml::from_str::<ShaderFile>(&text).ok()) else {
+        return Vec::new();
+    };
+    file.shaders
+        .iter()
+        .filter_map(|definition| definition.texture.as_ref())
+        .map(|texture| PathBuf::from(&texture.path))
+        .collect()
+}
+
+/// Interpreta el texto TOML de un archivo de definiciones.
+pub fn parse_shader_definitions(text: &str) -> Result<Vec<(String, LayerStack)>, String> {
+    let file: ShaderFile = toml::from_str(text).map_err(|err| err.to_string())?;
+    file.shaders
+        .into_iter()
+        .map(|definition| {
+            let stack = definition.build().map_err(|err| format!("shader '{}': {}", definition.name, err))?;
+            Ok((definition.name, stack))
+        })
+        .collect()
+}
+
+impl ShaderDefinition {
+    fn build(&self) -> Result<LayerStack, String> {
+        if !(0.0..=1.0).contains(&self.roughness) {
+            return Err(format!("roughness must be between 0 and 1, got {}", self.roughness));
+        }
+
+        let base = match (&self.base, &self.texture) {
+            (Some(base), _) => base.to_color()?,
+            (None, Some(_)) => Color::white(),
+            (None, None) => return Err("expected base, texture or both".to_string()),
+        };
+        let mut stack = LayerStack::new(base).with_specular(self.specular, self.roughness);
+        if let Some(texture) = &self.texture {
+            let (texture, sampler) = texture.build().map_err(|err| format!("texture: {}", err))?;
+            stack = stack.with_texture(texture, sampler);
+        }
+        if let Some(ambient) = &self.ambient {
+            stack = stack.with_ambient(ambient.to_color()?);
+        }
+        for (index, layer) in self.layers.iter().enumerate() {
+            // Las capas se numeran desde 1, como se leen en el archivo
+            stack = stack.with_layer(layer.build().map_err(|err| format!("layer {}: {}", index + 1, err))?);
+        }
+        Ok(stack)
+    }
+}
+
+impl TextureDefinition {
+    fn build(&self) -> Result<(Arc<Texture>, Sampler), String> {
+        let filter = Filter::from_name(&self.filter)
+            .ok_or_else(|| format!("Unknown filter '{}' (expected nearest or bilinear)", self.filter))?;
+        let wrap = |name: &str| {
+            WrapMode::from_name(name)
+                .ok_or_else(|| format!("Unknown wrap mode '{}' (expected repeat, mirrored_repeat or clamp)", name))
+        };
+        let sampler = Sampler {
+            filter,
+            wrap_u: wrap(&self.wrap_u)?,
+            wrap_v: wrap(&self.wrap_v)?,
+            mipmaps: self.mipmaps,
+        };
+
+        Ok((Arc::new(Texture::load(Path::new(&self.path))?), sampler))
+    }
+}
+
+impl LayerDefinition {
+    fn build(&self) -> Result<Layer, String> {
+        if !(0.0..=1.0).contains(&self.opacity) {
+            return Err(format!("opacity must be between 0 and 1, got {}", self.opacity));
+        }
+        let blend = LayerBlend::from_name(&self.blend).ok_or_else(|| {
+            format!("Unknown blend mode '{}' (expected normal, multiply, add, subtract or screen)", self.blend)
+        })?;
+        let gradient = match (&self.colors, &self.gradient) {
+            (Some(colors), None) => {
+                if colors.is_empty() {
+                    return Err("colors must have at least one entry".to_string());
+                }
+                let colors = colors.iter().map(ColorDefinition::to_color).collect::<Result<Vec<_>, _>>()?;
+                Gradient::evenly_spaced(&colors)
+            }
+            (None, Some(gradient)) => gradient.build()?,
+            _ => return Err("expected exactly one of colors or gradient".to_string()),
+        };
+
+        Ok(Layer::new(self.pattern.build()?, gradient)
+            .with_blend(blend)
+            .with_opacity(self.opacity))
+    }
+}
+
+impl PatternDefinition {
+    fn build(&self) -> Result<Pattern, String> {
+        Ok(match self {
+            PatternDefinition::Noise { noise, zoom, octaves, speed } => Pattern::Noise {
+                source: NoiseSource::from_name(noise)
+                    .ok_or_else(|| format!("Unknown noise '{}' (expected open_simplex or cellular)", noise))?,
+                zoom: *zoom,
+                octaves: (*octaves).max(1),
+                speed: *speed,
+            },
+            PatternDefinition::Bands { count, axis, turbulence, speed } => Pattern::Bands {
+                count: *count,
+                axis: to_axis(axis)?,
+                turbulence: *turbulence,
+                speed: *speed,
+            },
+            PatternDefinition::Cells { zoom, speed } => Pattern::Cells { zoom: *zoom, speed: *speed },
+            PatternDefinition::Gradient { axis } => Pattern::Gradient { axis: to_axis(axis)? },
+        })
+    }
+}
+
+impl GradientDefinition {
+    pub fn build(&self) -> Result<Gradient, String> {
+        if self.stops.is_empty() {
+            return Err("gradient must have at least one stop".to_string());
+        }
+        let interpolation = Interpolation::from_name(&self.interpolation).ok_or_else(|| {
+            format!("Unknown interpolation '{}' (expected linear, smoothstep or constant)", self.interpolation)
+        })?;
+        let space = ColorSpace::from_name(&self.space)
+            .ok_or_else(|| format!("Unknown color space '{}' (expected rgb, hsv or oklab)", self.space))?;
+
+        let stops = self.stops
+            .iter()
+            .map(|stop| {
+                if !stop.at.is_finite() {
+                    return Err(format!("gradient stop position must be a number, got {}", stop.at));
+                }
+                Ok((stop.at, stop.color.to_color()?))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(Gradient::new(stops).with_interpolation(interpolation).with_space(space))
+    }
+}
+
+impl ColorDefinition {
+    pub fn to_color(&self) -> Result<Color, String> {
+        match self {
+            ColorDefinition::Rgb([r, g, b]) => Ok(Color::new(*r, *g, *b)),
+            ColorDefinition::Hex(text) => text
+                .strip_prefix('#')
+                .filter(|digits| digits.len() == 6)
+                .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+                .map(Color::from_hex)
+                .ok_or_else(|| format!("Invalid color '{}' (expected [r, g, b] or \"#rrggbb\")", text)),
+        }
+    }
+}
+
+fn to_axis(axis: &[f32; 3]) -> Result<Vec3, String> {
+    let axis = Vec3::new(axis[0], axis[1], axis[2]);
+    if axis.magnitude() <= f32::EPSILON {
+        return Err("axis must not be zero".to_string());
+    }
+    Ok(axis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgb(color: Color) -> (u8, u8, u8) {
+        (color.r(), color.g(), color.b())
+    }
+
+    fn parse_one(text: &str) -> Result<LayerStack, String> {
+        let mut shaders = parse_shader_definitions(text)?;
+        assert_eq!(shaders.len(), 1);
+        Ok(shaders.remove(0).1)
+    }
+
+    // Shader `s` con una capa de ruido a la que cada prueba le agrega líneas
+    fn with_layer(extra: &str) -> String {
+        format!(
+            "[[shader]]\nname = \"s\"\nbase = [0, 0, 0]\n\n[[shader.layer]]\npattern = {{ type = \"noise\", zoom = 1.0 }}\n{}\n",
+            extra
+        )
+    }
+
+    fn error_of(text: &str) -> String {
+        match parse_shader_definitions(text) {
+            Ok(_) => panic!("the definitions should be rejected"),
+            Err(err) => err,
+        }
+    }
+
+    #[test]
+    fn bundled_definitions_are_registered() {
+        let mut registry = ShaderRegistry::new();
+        load_shader_definitions(Path::new(SHADERS_PATH), &mut registry).unwrap();
+        assert!(registry.get("ice_giant").is_ok());
+        assert!(registry.get("lava_world").is_ok());
+    }
+
+    #[test]
+    fn lists_texture_paths_even_if_they_fail_to_load() {
+        let path = std::env::temp_dir().join("space_travel_texture_paths_test.toml");
+        fs::write(
+            &path,
+            "[[shader]]\nname = \"a\"\ntexture = { path = \"assets/missing.png\" }\n\n[[shader]]\nname = \"b\"\nbase = [0, 0, 0]\n",
+        )
+        .unwrap();
+        let paths = texture_paths(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(paths, [PathBuf::from("assets/missing.png")]);
+        assert!(texture_paths(Path::new("assets/missing_shaders.toml")).is_empty());
+    }
+
+    #[test]
+    fn missing_file_names_the_path() {
+        let mut registry = ShaderRegistry::new();
+        let err = load_shader_definitions(Path::new("assets/missing_shaders.toml"), &mut registry).unwrap_err();
+        assert!(err.starts_with("assets/missing_shaders.toml: "));
+    }
+
+    #[test]
+    fn builds_layers_in_order() {
+        let stack = parse_one(
+            "[[shader]]\nname = \"s\"\nbase = \"#102030\"\nambient = [1, 2, 3]\nspecular = 0.5\nroughness = 0.25\n\n\
+             [[shader.layer]]\npattern = { type = \"bands\", count = 3.0 }\ncolors = [[0, 0, 0], [255, 255, 255]]\n\n\
+             [[shader.layer]]\npattern = { type = \"cells\", zoom = 2.0 }\n\
+             gradient = { stops = [{ at = 0.0, color = \"#000000\" }, { at = 1.0, color = \"#ffffff\" }], space = \"oklab\" }\n\
+             blend = \"add\"\nopacity = 0.5\n",
+        )
+        .unwrap();
+
+        assert_eq!(rgb(stack.base), (0x10, 0x20, 0x30));
+        assert_eq!(rgb(stack.ambient), (1, 2, 3));
+        assert_eq!((stack.specular, stack.roughness), (0.5, 0.25));
+        assert!(stack.texture.is_none());
+        assert_eq!(stack.layers.len(), 2);
+    }
+
+    #[test]
+    fn texture_defaults_base_to_white() {
+        let path = std::env::temp_dir().join("space_travel_shader_config_test.ppm");
+        fs::write(&path, b"P3\n1 1\n255\n10 20 30\n").unwrap();
+        let text = format!(
+            "[[shader]]\nname = \"s\"\ntexture = {{ path = {:?}, filter = \"nearest\", wrap_u = \"clamp\", mipmaps = false }}\n",
+            path.display().to_string()
+        );
+        let stack = parse_one(&text).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(rgb(stack.base), (255, 255, 255));
+        let (texture, sampler) = stack.texture.unwrap();
+        assert_eq!((texture.width(), texture.height()), (1, 1));
+        assert_eq!(sampler.filter, Filter::Nearest);
+        assert_eq!((sampler.wrap_u, sampler.wrap_v), (WrapMode::Clamp, WrapMode::Repeat));
+        assert!(!sampler.mipmaps);
+    }
+
+    #[test]
+    fn color_definitions() {
+        assert_eq!(rgb(ColorDefinition::Rgb([1, 2, 3]).to_color().unwrap()), (1, 2, 3));
+        assert_eq!(rgb(ColorDefinition::Hex("#FFa500".to_string()).to_color().unwrap()), (255, 165, 0));
+        for text in ["ffa500", "#ffa50", "#ffa5000", "#gga500", "#", ""] {
+            let err = ColorDefinition::Hex(text.to_string()).to_color().unwrap_err();
+            assert!(err.starts_with(&format!("Invalid color '{}'", text)), "{}", err);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_toml() {
+        assert!(parse_shader_definitions("[[shader]\nname = ").is_err());
+        assert!(error_of("[[shader]]\nname = \"s\"\nbase = [0, 0, 0]\nglow = 1.0\n").contains("unknown field"));
+        assert!(error_of("[[shader]]\nname = \"s\"\nbase = [256, 0, 0]\n").contains("did not match any variant"));
+        assert!(error_of(&with_layer("colors = [[0, 0, 0]]").replace("\"noise\"", "\"stripes\"")).contains("unknown variant"));
+    }
+
+    #[test]
+    fn rejects_invalid_shaders() {
+        assert_eq!(error_of("[[shader]]\nname = \"s\"\n"), "shader 's': expected base, texture or both");
+        assert!(error_of("[[shader]]\nname = \"s\"\nbase = [0, 0, 0]\nroughness = 2.0\n").contains("roughness must be between 0 and 1"));
+        assert!(error_of("[[shader]]\nname = \"s\"\nbase = \"red\"\n").contains("Invalid color 'red'"));
+    }
+
+    #[test]
+    fn rejects_invalid_textures() {
+        let texture = |fields: &str| format!("[[shader]]\nname = \"s\"\ntexture = {{ path = \"assets/missing.png\"{} }}\n", fields);
+        assert!(error_of(&texture("")).starts_with("shader 's': texture: assets/missing.png: "));
+        assert!(error_of(&texture(", filter = \"trilinear\"")).contains("Unknown filter 'trilinear'"));
+        assert!(error_of(&texture(", wrap_v = \"mirror\"")).contains("Unknown wrap mode 'mirror'"));
+    }
+
+    #[test]
+    fn rejects_invalid_layers() {
+        assert!(error_of(&with_layer("")).contains("layer 1: expected exactly one of colors or gradient"));
+        assert!(error_of(&with_layer("colors = []")).contains("colors must have at least one entry"));
+        assert!(error_of(&with_layer("colors = [[0, 0, 0]]\ngradient = { stops = [] }")).contains("expected exactly one of colors or gradient"));
+        assert!(error_of(&with_layer("colors = [[0, 0, 0]]\nopacity = 1.5")).contains("opacity must be between 0 and 1"));
+        assert!(error_of(&with_layer("colors = [[0, 0, 0]]\nblend = \"overlay\"")).contains("Unknown blend mode 'overlay'"));
+        assert!(error_of(&with_layer("colors = [[0, 0, 0]]").replace("type = \"noise\"", "type = \"noise\", noise = \"perlin\""))
+            .contains("Unknown noise 'perlin'"));
+        assert!(error_of(&with_layer("colors = [[0, 0, 0]]").replace("{ type = \"noise\", zoom = 1.0 }", "{ type = \"gradient\", axis = [0.0, 0.0, 0.0] }"))
+            .contains("axis must not be zero"));
+    }
+
+    #[test]
+    fn rejects_invalid_gradients() {
+        let gradient = |fields: &str| with_layer(&format!("gradient = {{ {} }}", fields));
+        let stops = "stops = [{ at = 0.0, color = [0, 0, 0] }]";
+        assert!(error_of(&gradient("stops = []")).contains("gradient must have at least one stop"));
+        assert!(error_of(&gradient(&format!("{}, interpolation = \"cubic\"", stops))).contains("Unknown interpolation 'cubic'"));
+        assert!(error_of(&gradient(&format!("{}, space = \"lab\"", stops))).contains("Unknown color space 'lab'"));
+        assert!(error_of(&gradient("stops = [{ at = nan, color = [0, 0, 0] }]")).contains("gradient stop position must be a number"));
+    }
+}
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::color::{Color, HdrColor};
+use crate::fragment::{self, Fragment};
+use crate::experimental_shaders;
+use crate::material::{self, Material};
+use crate::Uniforms;
+
+/// Shader de fragmentos: calcula la radiancia final de cada fragmento.
+/// Se comparte entre los hilos del rasterizador, por eso debe ser `Send + Sync`.
+pub trait FragmentShader: Send + Sync {
+    fn shade(&self, fragment: &Fragment, uniforms: &Uniforms) -> HdrColor;
+
+    /// Si el shader lee `Fragment::tex_coords_slope`; si no, el rasterizador no la calcula.
+    fn uses_texture_derivatives(&self) -> bool {
+        false
+    }
+}
+
+/// Shader que solo describe la superficie; la iluminación común de `material::shade` hace el resto.
+pub trait SurfaceShader: Send + Sync {
+    fn surface(&self, fragment: &Fragment, uniforms: &Uniforms) -> Material;
+
+    /// Igual que `FragmentShader::uses_texture_derivatives`.
+    fn uses_texture_derivatives(&self) -> bool {
+        false
+    }
+}
+
+impl<T: SurfaceShader> FragmentShader for T {
+    fn shade(&self, fragment: &Fragment, uniforms: &Uniforms) -> HdrColor {
+        let material = self.surface(fragment, uniforms);
+        material::shade(&material, fragment, &uniforms.lights, uniforms.lighting)
+    }
+
+    fn uses_texture_derivatives(&self) -> bool {
+        SurfaceShader::uses_texture_derivatives(self)
+    }
+}
+
+/// Shader sin parámetros que devuelve directamente el color final, sin la etapa de iluminación.
+pub struct Unlit(pub fn(&Fragment, &Uniforms) -> Color);
+
+impl FragmentShader for Unlit {
+    fn shade(&self, fragment: &Fragment, uniforms: &Uniforms) -> HdrColor {
+        (self.0)(fragment, uniforms).into()
+    }
+}
+
+/// Shaders disponibles por nombre, con sus parámetros ya fijados.
+#[derive(Default)]
+pub struct ShaderRegistry {
+    shaders: HashMap<String, Arc<dyn FragmentShader>>,
+}
+
+impl ShaderRegistry {
+    pub fn new() -> Self {
+        ShaderRegistry { shaders: HashMap::new() }
+    }
+
+    /// Registro con los shaders de `fragment` y `experimental_shaders`.
+    pub fn with_builtin_shaders() -> Self {
+        let mut registry = ShaderRegistry::new();
+        fragment::register_shaders(&mut registry);
+        experimental_shaders::register_shaders(&mut registry);
+        registry
+    }
+
+    /// Registra `shader` con `name`, reemplazando al que tuviera ese nombre.
+    pub fn register(&mut self, name: &str, shader: impl FragmentShader + 'static) {
+        self.shaders.insert(name.to_string(), Arc::new(shader));
+    }
+
+    pub fn get(&self, name: &str) -> Result<Arc<dyn FragmentShader>, String> {
+        self.shaders.get(name).cloned().ok_or_else(|| {
+            let mut names: Vec<&str> = self.shaders.keys().map(|name| name.as_str()).collect();
+            names.sort_unstable();
+            format!("Unknown shader '{}' (available: {})", name, names.join(", "))
+        })
+    }
+}
@@ -1,4 +1,4 @@
-use nalgebra_glm::{Vec3, Vec4};
+use nalgebra_glm::{Mat3, Mat4, Vec3, Vec4};
 use crate::vertex::Vertex;
 use crate::Uniforms;
 
@@ -11,19 +11,14 @@ pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
   );
   let transformed = uniforms.model_matrix * position;
 
+  // Las normales usan la inversa transpuesta para seguir perpendiculares con escalas no uniformes
+  let transformed_normal = normal_matrix(&uniforms.model_matrix) * vertex.normal;
+
   // Transforma al espacio de vista
   let view_transformed = uniforms.view_matrix * transformed;
 
-  // Transforma al espacio de clip
+  // Transforma al espacio de clip; la división por `w` se hace después del recorte
   let clip_transformed = uniforms.projection_matrix * view_transformed;
-  let screen_position = uniforms.viewport_matrix * clip_transformed;
-
-  // Divide por `w` para normalizar en el espacio NDC
-  let ndc_position = Vec3::new(
-      clip_transformed.x / clip_transformed.w,
-      clip_transformed.y / clip_transformed.w,
-      clip_transformed.z / clip_transformed.w,
-  );
 
   // Devuelve el vértice transformado
   Vertex {
@@ -31,7 +26,15 @@ pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
       normal: vertex.normal,                   
       tex_coords: vertex.tex_coords,           
       color: vertex.color,                    
-      transformed_position: ndc_position,      
-      transformed_normal: vertex.normal,       
+      clip_position: clip_transformed,
+      world_position: transformed.xyz(),
+      transformed_position: Vec3::new(0.0, 0.0, 0.0),
+      transformed_normal,
   }
 }
+
+// Inversa transpuesta de la parte lineal de la matriz de modelo
+pub fn normal_matrix(model_matrix: &Mat4) -> Mat3 {
+  let linear: Mat3 = model_matrix.fixed_view::<3, 3>(0, 0).into();
+  linear.try_inverse().map(|inverse| inverse.transpose()).unwrap_or(linear)
+}
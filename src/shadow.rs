@@ -0,0 +1,88 @@
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use crate::framebuffer::Framebuffer;
+
+// Resolución por lado de cada mapa de sombras
+pub const SHADOW_MAP_SIZE: usize = 512;
+
+// Radio en texels del filtro PCF: (2r + 1)^2 comparaciones por fragmento
+const PCF_RADIUS: i32 = 1;
+
+// Desplazamientos contra el acné de sombra, en texels: a lo largo de la normal y en profundidad
+const NORMAL_OFFSET: f32 = 1.5;
+const DEPTH_BIAS: f32 = 1.0;
+
+/// Mapa de sombras direccional de la luz del Sol, centrado en un cuerpo y lo que lo rodea.
+/// Guarda solo profundidad, vista desde la luz con proyección ortográfica.
+pub struct ShadowMap {
+    pub depth: Framebuffer,
+    pub view_matrix: Mat4,
+    pub projection_matrix: Mat4,
+    pub viewport_matrix: Mat4,
+    // De mundo a texels del mapa (x, y) y profundidad desde la luz (z)
+    light_matrix: Mat4,
+    // Dirección unitaria de la luz, desde el Sol hacia el cuerpo
+    light_direction: Vec3,
+    // Tamaño de un texel en unidades de mundo
+    texel_size: f32,
+}
+
+impl ShadowMap {
+    /// Mapa que cubre la esfera de radio `radius` alrededor de `target`, iluminada desde `light_position`.
+    pub fn new(light_position: Vec3, target: Vec3, radius: f32) -> Self {
+        let light_direction = (target - light_position).normalize();
+        let eye = target - light_direction * radius * 2.0;
+
+        // Cualquier eje que no sea paralelo a la luz sirve como "arriba"
+        let up = if light_direction.z.abs() < 0.9 { Vec3::z() } else { Vec3::x() };
+        let (near, far) = (radius, radius * 3.0);
+        let size = SHADOW_MAP_SIZE as f32;
+        let half_depth = (far - near) / 2.0;
+
+        let view_matrix = nalgebra_glm::look_at_lh(&eye, &target, &up);
+        let projection_matrix = nalgebra_glm::ortho_lh_no(-radius, radius, -radius, radius, near, far);
+        // Igual que el viewport de la cámara: x, y en texels y z en unidades de mundo
+        let viewport_matrix = Mat4::new(
+            size / 2.0, 0.0, 0.0, size / 2.0,
+            0.0, size / 2.0, 0.0, size / 2.0,
+            0.0, 0.0, half_depth, near + half_depth,
+            0.0, 0.0, 0.0, 1.0,
+        );
+
+        ShadowMap {
+            depth: Framebuffer::depth_only(SHADOW_MAP_SIZE, SHADOW_MAP_SIZE),
+            view_matrix,
+            projection_matrix,
+            viewport_matrix,
+            light_matrix: viewport_matrix * projection_matrix * view_matrix,
+            light_direction,
+            texel_size: radius * 2.0 / size,
+        }
+    }
+
+    /// Fracción de la luz que llega a un punto (1 = iluminado, 0 = en sombra), con filtrado PCF.
+    pub fn visibility(&self, world_position: &Vec3, world_normal: &Vec3) -> f32 {
+        // Se desplaza el punto hacia la luz a lo largo de la normal para no sombrearse a sí mismo
+        let facing = if world_normal.dot(&self.light_direction) <= 0.0 { 1.0 } else { -1.0 };
+        let offset_position = world_position + world_normal * (facing * NORMAL_OFFSET * self.texel_size);
+
+        let p = self.light_matrix * Vec4::new(offset_position.x, offset_position.y, offset_position.z, 1.0);
+        let (center_x, center_y) = (p.x.floor() as i32, p.y.floor() as i32);
+        let depth = p.z - DEPTH_BIAS * self.texel_size;
+
+        let mut lit = 0;
+        let mut total = 0;
+        for dy in -PCF_RADIUS..=PCF_RADIUS {
+            for dx in -PCF_RADIUS..=PCF_RADIUS {
+                let (x, y) = (center_x + dx, center_y + dy);
+                total += 1;
+                // Fuera del mapa no hay nada que proyecte sombra
+                let inside = x >= 0 && y >= 0 && (x as usize) < self.depth.width && (y as usize) < self.depth.height;
+                if !inside || depth <= self.depth.depth(x as usize, y as usize) {
+                    lit += 1;
+                }
+            }
+        }
+
+        lit as f32 / total as f32
+    }
+}
@@ -1,5 +1,6 @@
 use nalgebra_glm::{Vec3, Vec4};
 use rand::prelude::*;
+use rand::rngs::StdRng;
 use std::f32::consts::PI;
 use crate::{Framebuffer, Uniforms};
 
@@ -13,9 +14,12 @@ pub struct Skybox {
     stars: Vec<Star>,
 }
 
+// Semilla fija para que los cuadros renderizados sin ventana sean reproducibles
+const SKYBOX_SEED: u64 = 42;
+
 impl Skybox {
     pub fn new(star_count: usize) -> Self {
-        let mut rng = rand::thread_rng();
+        let mut rng = StdRng::seed_from_u64(SKYBOX_SEED);
         let mut stars = Vec::with_capacity(star_count);
 
         for _ in 0..star_count {
@@ -0,0 +1,593 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use nalgebra_glm::Vec2;
+use crate::color::{Color, HdrColor};
+
+/// Cómo se elige el color entre los texeles vecinos.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Filter {
+    Nearest,
+    // Bilineal dentro de cada nivel y, con mipmaps, lineal entre niveles
+    Bilinear,
+}
+
+impl Filter {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "nearest" => Some(Filter::Nearest),
+            "bilinear" => Some(Filter::Bilinear),
+            _ => None,
+        }
+    }
+}
+
+/// Qué pasa con las coordenadas de textura fuera de [0, 1].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WrapMode {
+    Repeat,
+    MirroredRepeat,
+    // Repite el texel del borde
+    Clamp,
+}
+
+impl WrapMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "repeat" => Some(WrapMode::Repeat),
+            "mirrored_repeat" => Some(WrapMode::MirroredRepeat),
+            "clamp" => Some(WrapMode::Clamp),
+            _ => None,
+        }
+    }
+
+    // Índice de texel válido para `index` en un eje de `size` texeles
+    fn apply(&self, index: i64, size: usize) -> usize {
+        let size = size as i64;
+        let wrapped = match self {
+            WrapMode::Repeat => index.rem_euclid(size),
+            WrapMode::MirroredRepeat => {
+                let period = index.rem_euclid(2 * size);
+                if period < size { period } else { 2 * size - 1 - period }
+            }
+            WrapMode::Clamp => index.clamp(0, size - 1),
+        };
+        wrapped as usize
+    }
+}
+
+/// Forma de leer una textura: filtro, repetición en u y en v, y si se usan los mipmaps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sampler {
+    pub filter: Filter,
+    pub wrap_u: WrapMode,
+    pub wrap_v: WrapMode,
+    pub mipmaps: bool,
+}
+
+impl Default for Sampler {
+    fn default() -> Self {
+        Sampler {
+            filter: Filter::Bilinear,
+            wrap_u: WrapMode::Repeat,
+            wrap_v: WrapMode::Repeat,
+            mipmaps: true,
+        }
+    }
+}
+
+// Un nivel de la cadena de mipmaps
+struct MipLevel {
+    width: usize,
+    height: usize,
+    texels: Vec<HdrColor>,
+}
+
+impl MipLevel {
+    fn texel(&self, x: i64, y: i64, sampler: &Sampler) -> HdrColor {
+        let x = sampler.wrap_u.apply(x, self.width);
+        let y = sampler.wrap_v.apply(y, self.height);
+        self.texels[y * self.width + x]
+    }
+
+    fn sample(&self, uv: Vec2, sampler: &Sampler) -> HdrColor {
+        let x = uv.x * self.width as f32;
+        let y = uv.y * self.height as f32;
+        match sampler.filter {
+            Filter::Nearest => self.texel(x.floor() as i64, y.floor() as i64, sampler),
+            Filter::Bilinear => {
+                // Los centros de los texeles están a medio texel de sus bordes
+                let (x, y) = (x - 0.5, y - 0.5);
+                let (x0, y0) = (x.floor(), y.floor());
+                let (fx, fy) = (x - x0, y - y0);
+                let (x0, y0) = (x0 as i64, y0 as i64);
+
+                let top = self.texel(x0, y0, sampler).lerp(&self.texel(x0 + 1, y0, sampler), fx);
+                let bottom = self.texel(x0, y0 + 1, sampler).lerp(&self.texel(x0 + 1, y0 + 1, sampler), fx);
+                top.lerp(&bottom, fy)
+            }
+        }
+    }
+
+    // Nivel siguiente: cada texel promedia hasta 2x2 texeles de este
+    fn downsample(&self) -> MipLevel {
+        let width = (self.width / 2).max(1);
+        let height = (self.height / 2).max(1);
+        let mut texels = Vec::with_capacity(width * height);
+
+        for y in 0..height {
+            for x in 0..width {
+                let (x0, y0) = (x * 2, y * 2);
+                let (x1, y1) = ((x0 + 1).min(self.width - 1), (y0 + 1).min(self.height - 1));
+                let sum = self.texels[y0 * self.width + x0]
+                    + self.texels[y0 * self.width + x1]
+                    + self.texels[y1 * self.width + x0]
+                    + self.texels[y1 * self.width + x1];
+                // La suma de `HdrColor` toma el mayor alfa; el promedio lo recalcula aparte
+                let alpha = (self.texels[y0 * self.width + x0].a
+                    + self.texels[y0 * self.width + x1].a
+                    + self.texels[y1 * self.width + x0].a
+                    + self.texels[y1 * self.width + x1].a) * 0.25;
+                texels.push((sum * 0.25).with_alpha(alpha));
+            }
+        }
+
+        MipLevel { width, height, texels }
+    }
+}
+
+/// Imagen para muestrear con coordenadas de textura, con su cadena de mipmaps ya calculada.
+/// `(0, 0)` es la esquina superior izquierda; `Obj` ya invierte la `v` de los archivos OBJ para que coincida.
+pub struct Texture {
+    // Del tamaño original a 1x1
+    levels: Vec<MipLevel>,
+}
+
+impl fmt::Debug for Texture {
+    // Sin los texeles, que pueden ser millones
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Texture")
+            .field("width", &self.width())
+            .field("height", &self.height())
+            .field("levels", &self.levels.len())
+            .finish()
+    }
+}
+
+impl Texture {
+    /// Textura de `width` x `height` a partir de sus píxeles por filas, de arriba hacia abajo.
+    pub fn from_pixels(width: usize, height: usize, pixels: &[Color]) -> Result<Self, String> {
+        if width == 0 || height == 0 {
+            return Err("texture must not be empty".to_string());
+        }
+        if pixels.len() != width * height {
+            return Err(format!("expected {} pixels for {}x{}, got {}", width * height, width, height, pixels.len()));
+        }
+
+        let mut levels = vec![MipLevel {
+            width,
+            height,
+            texels: pixels.iter().map(|&pixel| HdrColor::from(pixel)).collect(),
+        }];
+        while let Some(last) = levels.last().filter(|level| level.width > 1 || level.height > 1) {
+            let next = last.downsample();
+            levels.push(next);
+        }
+
+        Ok(Texture { levels })
+    }
+
+    /// Carga una imagen PNG, PPM (P3 o P6) o TGA según la extensión de `path`.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        let extension = path.extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+
+        let decoded = match extension.as_deref() {
+            Some("png") => decode_png(&bytes),
+            Some("ppm") => decode_ppm(&bytes),
+            Some("tga") => decode_tga(&bytes),
+            _ => Err("Unsupported texture format (expected .png, .ppm or .tga)".to_string()),
+        };
+        let (width, height, pixels) = decoded.map_err(|err| format!("{}: {}", path.display(), err))?;
+        Texture::from_pixels(width, height, &pixels).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    pub fn width(&self) -> usize {
+        self.levels[0].width
+    }
+
+    pub fn height(&self) -> usize {
+        self.levels[0].height
+    }
+
+    /// Color en `uv` del nivel de mayor detalle.
+    pub fn sample(&self, uv: Vec2, sampler: &Sampler) -> Color {
+        self.levels[0].sample(uv, sampler).to_color()
+    }
+
+    /// Color en `uv` con el nivel de mipmap que corresponde a la variación de `uv` por píxel en x (`duv_dx`) e y (`duv_dy`).
+    pub fn sample_grad(&self, uv: Vec2, duv_dx: Vec2, duv_dy: Vec2, sampler: &Sampler) -> Color {
+        if !sampler.mipmaps {
+            return self.sample(uv, sampler);
+        }
+
+        // Texeles del nivel 0 que abarca un píxel en pantalla
+        let size = Vec2::new(self.width() as f32, self.height() as f32);
+        let footprint = duv_dx.component_mul(&size).magnitude().max(duv_dy.component_mul(&size).magnitude());
+        let max_level = (self.levels.len() - 1) as f32;
+        let lod = if footprint > 1.0 { footprint.log2().min(max_level) } else { 0.0 };
+
+        let color = match sampler.filter {
+            Filter::Nearest => self.levels[lod.round() as usize].sample(uv, sampler),
+            Filter::Bilinear => {
+                let level = lod.floor() as usize;
+                let fine = self.levels[level].sample(uv, sampler);
+                match self.levels.get(level + 1) {
+                    Some(coarse) => fine.lerp(&coarse.sample(uv, sampler), lod - level as f32),
+                    None => fine,
+                }
+            }
+        };
+        color.to_color()
+    }
+}
+
+type Image = (usize, usize, Vec<Color>);
+
+// Lado máximo de una imagen; un encabezado dañado no llega a reservar memoria desmedida
+const MAX_TEXTURE_SIZE: usize = 16384;
+
+// Cantidad de píxeles de una imagen de `width` x `height`, si su tamaño es válido
+fn pixel_count(width: usize, height: usize) -> Result<usize, String> {
+    if !(1..=MAX_TEXTURE_SIZE).contains(&width) || !(1..=MAX_TEXTURE_SIZE).contains(&height) {
+        return Err(format!("invalid image size {}x{} (each side must be between 1 and {})", width, height, MAX_TEXTURE_SIZE));
+    }
+    width.checked_mul(height).ok_or_else(|| format!("image size {}x{} is too large", width, height))
+}
+
+fn decode_png(bytes: &[u8]) -> Result<Image, String> {
+    let mut decoder = png::Decoder::new(bytes);
+    // Paleta y grises de pocos bits a 8 bits por canal; 16 bits se recortan a 8
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(|err| err.to_string())?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).map_err(|err| err.to_string())?;
+
+    let channels = match info.color_type {
+        png::ColorType::Grayscale => 1,
+        png::ColorType::GrayscaleAlpha => 2,
+        png::ColorType::Rgb => 3,
+        png::ColorType::Rgba => 4,
+        png::ColorType::Indexed => return Err("indexed PNG was not expanded".to_string()),
+    };
+    let (width, height) = (info.width as usize, info.height as usize);
+
+    let mut pixels = Vec::with_capacity(pixel_count(width, height)?);
+    for row in buffer.chunks(info.line_size).take(height) {
+        for texel in row.chunks(channels).take(width) {
+            pixels.push(match texel {
+                [v] => Color::new(*v, *v, *v),
+                [v, a] => Color::new_rgba(*v, *v, *v, *a),
+                [r, g, b] => Color::new(*r, *g, *b),
+                [r, g, b, a] => Color::new_rgba(*r, *g, *b, *a),
+                _ => unreachable!("chunks of 1 to 4 channels"),
+            });
+        }
+    }
+    Ok((width, height, pixels))
+}
+
+// PPM binario (P6) o de texto (P3), con cualquier valor máximo hasta 65535
+fn decode_ppm(bytes: &[u8]) -> Result<Image, String> {
+    let mut position = 0;
+
+    let magic = next_ppm_token(bytes, &mut position).ok_or("empty PPM file")?;
+    let mut header = || -> Result<usize, String> {
+        let token = next_ppm_token(bytes, &mut position).ok_or("truncated PPM header")?;
+        token.parse().map_err(|_| format!("invalid PPM header value '{}'", token))
+    };
+    let (width, height, max_value) = (header()?, header()?, header()?);
+    if max_value == 0 || max_value > 65535 {
+        return Err(format!("invalid PPM maximum value {}", max_value));
+    }
+    let scale = |value: usize| ((value.min(max_value) * 255 + max_value / 2) / max_value) as u8;
+    let sample_count = pixel_count(width, height)?.checked_mul(3).ok_or("PPM image is too large")?;
+    let remaining = bytes.len().saturating_sub(position);
+
+    let samples: Vec<usize> = match magic.as_str() {
+        "P3" => {
+            // Cada valor ocupa al menos un dígito y el espacio que lo precede
+            if remaining < sample_count.saturating_mul(2) {
+                return Err("truncated PPM pixel data".to_string());
+            }
+            let mut samples = Vec::with_capacity(sample_count);
+            for _ in 0..sample_count {
+                let token = next_ppm_token(bytes, &mut position).ok_or("truncated PPM pixel data")?;
+                samples.push(token.parse().map_err(|_| format!("invalid PPM value '{}'", token))?);
+            }
+            samples
+        }
+        "P6" => {
+            // Un único espacio separa el encabezado de los datos
+            let data = bytes.get(position + 1..).unwrap_or(&[]);
+            let bytes_per_sample = if max_value < 256 { 1 } else { 2 };
+            if data.len() < sample_count.saturating_mul(bytes_per_sample) {
+                return Err("truncated PPM pixel data".to_string());
+            }
+            data.chunks(bytes_per_sample)
+                .take(sample_count)
+                .map(|sample| sample.iter().fold(0, |value, &byte| (value << 8) | byte as usize))
+                .collect()
+        }
+        _ => return Err(format!("unsupported PPM type '{}' (expected P3 or P6)", magic)),
+    };
+
+    let pixels = samples.chunks(3).map(|rgb| Color::new(scale(rgb[0]), scale(rgb[1]), scale(rgb[2]))).collect();
+    Ok((width, height, pixels))
+}
+
+// Siguiente palabra desde `position`, saltando espacios y comentarios
+fn next_ppm_token(bytes: &[u8], position: &mut usize) -> Option<String> {
+    loop {
+        while *position < bytes.len() && bytes[*position].is_ascii_whitespace() {
+            *position += 1;
+        }
+        if *position < bytes.len() && bytes[*position] == b'#' {
+            while *position < bytes.len() && bytes[*position] != b'\n' {
+                *position += 1;
+            }
+        } else {
+            break;
+        }
+    }
+    let start = *position;
+    while *position < bytes.len() && !bytes[*position].is_ascii_whitespace() {
+        *position += 1;
+    }
+    (*position > start).then(|| String::from_utf8_lossy(&bytes[start..*position]).into_owned())
+}
+
+// TGA de color verdadero (24 o 32 bits) o de grises (8 bits), con o sin compresión RLE
+fn decode_tga(bytes: &[u8]) -> Result<Image, String> {
+    if bytes.len() < 18 {
+        return Err("truncated TGA header".to_string());
+    }
+    let id_length = bytes[0] as usize;
+    let color_map_type = bytes[1];
+    let image_type = bytes[2];
+    let width = u16::from_le_bytes([bytes[12], bytes[13]]) as usize;
+    let height = u16::from_le_bytes([bytes[14], bytes[15]]) as usize;
+    let bits_per_pixel = bytes[16];
+    let descriptor = bytes[17];
+
+    let count = pixel_count(width, height)?;
+    if color_map_type != 0 {
+        return Err("color-mapped TGA is not supported".to_string());
+    }
+    let (grayscale, rle) = match image_type {
+        2 => (false, false),
+        3 => (true, false),
+        10 => (false, true),
+        11 => (true, true),
+        _ => return Err(format!("unsupported TGA image type {}", image_type)),
+    };
+    let bytes_per_pixel = match (grayscale, bits_per_pixel) {
+        (true, 8) => 1,
+        (false, 24) => 3,
+        (false, 32) => 4,
+        _ => return Err(format!("unsupported TGA pixel depth {}", bits_per_pixel)),
+    };
+
+    let mut data = bytes.get(18 + id_length..).ok_or("truncated TGA data")?;
+    let raw_size = count * bytes_per_pixel;
+    // Sin RLE cada píxel ocupa `bytes_per_pixel`; con RLE, un paquete de 1 + `bytes_per_pixel` bytes da a lo sumo 128 píxeles
+    let min_data_size = if rle { count.div_ceil(128) * (1 + bytes_per_pixel) } else { raw_size };
+    if data.len() < min_data_size {
+        return Err("truncated TGA data".to_string());
+    }
+    let mut raw = Vec::with_capacity(raw_size);
+    if rle {
+        // Paquetes: un byte de cabecera y luego un píxel repetido o una tira de píxeles
+        while raw.len() < raw_size {
+            let (&packet, rest) = data.split_first().ok_or("truncated TGA data")?;
+            let length = (packet & 0x7F) as usize + 1;
+            if packet & 0x80 != 0 {
+                let pixel = rest.get(..bytes_per_pixel).ok_or("truncated TGA data")?;
+                for _ in 0..length {
+                    raw.extend_from_slice(pixel);
+                }
+                data = &rest[bytes_per_pixel..];
+            } else {
+                let pixels = rest.get(..length * bytes_per_pixel).ok_or("truncated TGA data")?;
+                raw.extend_from_slice(pixels);
+                data = &rest[length * bytes_per_pixel..];
+            }
+        }
+        raw.truncate(raw_size);
+    } else {
+        raw.extend_from_slice(&data[..raw_size]);
+    }
+
+    // Los canales vienen en orden BGR(A)
+    let rows: Vec<Vec<Color>> = raw
+        .chunks(width * bytes_per_pixel)
+        .map(|row| {
+            row.chunks(bytes_per_pixel)
+                .map(|texel| match texel {
+                    [v] => Color::new(*v, *v, *v),
+                    [b, g, r] => Color::new(*r, *g, *b),
+                    [b, g, r, a] => Color::new_rgba(*r, *g, *b, *a),
+                    _ => unreachable!("1, 3 or 4 bytes per pixel"),
+                })
+                .collect()
+        })
+        .collect();
+
+    // El bit 5 del descriptor indica filas de arriba hacia abajo; si no, la primera fila es la de abajo
+    let top_to_bottom = descriptor & 0x20 != 0;
+    let pixels = if top_to_bottom {
+        rows.into_iter().flatten().collect()
+    } else {
+        rows.into_iter().rev().flatten().collect()
+    };
+    Ok((width, height, pixels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgba(pixels: &[Color]) -> Vec<(u8, u8, u8, u8)> {
+        pixels.iter().map(|pixel| (pixel.r(), pixel.g(), pixel.b(), pixel.a())).collect()
+    }
+
+    // Encabezado TGA sin identificador ni paleta
+    fn tga(image_type: u8, width: u16, height: u16, bits_per_pixel: u8, descriptor: u8, data: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0, 0, image_type, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        bytes.extend_from_slice(&width.to_le_bytes());
+        bytes.extend_from_slice(&height.to_le_bytes());
+        bytes.extend_from_slice(&[bits_per_pixel, descriptor]);
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    #[test]
+    fn wrap_modes() {
+        let indices = [-5, -1, 0, 3, 4, 5, 8];
+        let wrapped = |mode: WrapMode| indices.map(|index| mode.apply(index, 4));
+        assert_eq!(wrapped(WrapMode::Repeat), [3, 3, 0, 3, 0, 1, 0]);
+        assert_eq!(wrapped(WrapMode::MirroredRepeat), [3, 0, 0, 3, 3, 2, 0]);
+        assert_eq!(wrapped(WrapMode::Clamp), [0, 0, 0, 3, 3, 3, 3]);
+        assert_eq!(WrapMode::MirroredRepeat.apply(-1, 1), 0);
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(WrapMode::from_name("mirrored_repeat"), Some(WrapMode::MirroredRepeat));
+        assert_eq!(WrapMode::from_name("mirror"), None);
+        assert_eq!(Filter::from_name("nearest"), Some(Filter::Nearest));
+        assert_eq!(Filter::from_name("trilinear"), None);
+    }
+
+    #[test]
+    fn decodes_text_ppm() {
+        let (width, height, pixels) = decode_ppm(b"P3\n# comentario\n2 1 # otro\n15\n15 0 0  0 15 7\n").unwrap();
+        assert_eq!((width, height), (2, 1));
+        assert_eq!(rgba(&pixels), [(255, 0, 0, 255), (0, 255, 119, 255)]);
+    }
+
+    #[test]
+    fn decodes_binary_ppm() {
+        let (width, height, pixels) = decode_ppm(b"P6 1 2 255\n\x01\x02\x03\xff\x80\x00").unwrap();
+        assert_eq!((width, height), (1, 2));
+        assert_eq!(rgba(&pixels), [(1, 2, 3, 255), (255, 128, 0, 255)]);
+
+        let (_, _, pixels) = decode_ppm(b"P6 1 1 65535\n\xff\xff\x80\x00\x00\x00").unwrap();
+        assert_eq!(rgba(&pixels), [(255, 128, 0, 255)]);
+    }
+
+    #[test]
+    fn rejects_malformed_ppm() {
+        let error_of = |bytes: &[u8]| decode_ppm(bytes).unwrap_err();
+        assert_eq!(error_of(b""), "empty PPM file");
+        assert_eq!(error_of(b"P6 2"), "truncated PPM header");
+        assert_eq!(error_of(b"P6 2 x 255"), "invalid PPM header value 'x'");
+        assert_eq!(error_of(b"P5 1 1 255\n\x00"), "unsupported PPM type 'P5' (expected P3 or P6)");
+        assert_eq!(error_of(b"P6 1 1 0\n\x00\x00\x00"), "invalid PPM maximum value 0");
+        assert_eq!(error_of(b"P6 1 1 70000\n"), "invalid PPM maximum value 70000");
+        assert!(error_of(b"P6 0 1 255\n").starts_with("invalid image size 0x1"));
+        assert_eq!(error_of(b"P6 2 1 255\n\x00\x00\x00"), "truncated PPM pixel data");
+        assert_eq!(error_of(b"P6 1 1 65535\n\x00\x00\x00"), "truncated PPM pixel data");
+        assert_eq!(error_of(b"P3 2 1 255\n0 0 0 0 0"), "truncated PPM pixel data");
+        assert_eq!(error_of(b"P3 1 1 255\n0 red 0"), "invalid PPM value 'red'");
+    }
+
+    #[test]
+    fn rejects_oversized_ppm_before_allocating() {
+        assert!(decode_ppm(b"P6 16385 1 255\n").unwrap_err().starts_with("invalid image size 16385x1"));
+        assert!(decode_ppm(b"P3 18446744073709551615 18446744073709551615 255\n").unwrap_err().starts_with("invalid image size"));
+        // Tamaño válido pero sin datos: falla antes de reservar los 16384 x 16384 píxeles
+        assert_eq!(decode_ppm(b"P6 16384 16384 255\n").unwrap_err(), "truncated PPM pixel data");
+        assert_eq!(decode_ppm(b"P3 16384 16384 255\n0").unwrap_err(), "truncated PPM pixel data");
+    }
+
+    #[test]
+    fn decodes_uncompressed_tga() {
+        // De abajo hacia arriba: la primera fila del archivo es la de abajo
+        let bytes = tga(2, 2, 2, 24, 0, &[
+            0, 0, 255, 0, 255, 0,
+            255, 0, 0, 10, 20, 30,
+        ]);
+        let (width, height, pixels) = decode_tga(&bytes).unwrap();
+        assert_eq!((width, height), (2, 2));
+        assert_eq!(rgba(&pixels), [(0, 0, 255, 255), (30, 20, 10, 255), (255, 0, 0, 255), (0, 255, 0, 255)]);
+
+        let bytes = tga(2, 1, 2, 32, 0x20, &[1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(rgba(&decode_tga(&bytes).unwrap().2), [(3, 2, 1, 4), (7, 6, 5, 8)]);
+    }
+
+    #[test]
+    fn decodes_rle_tga() {
+        // Un paquete repetido de 3 píxeles y uno literal de 2, en grises y de arriba hacia abajo
+        let bytes = tga(11, 5, 1, 8, 0x20, &[0x82, 50, 0x01, 100, 200]);
+        let (_, _, pixels) = decode_tga(&bytes).unwrap();
+        assert_eq!(rgba(&pixels), [(50, 50, 50, 255), (50, 50, 50, 255), (50, 50, 50, 255), (100, 100, 100, 255), (200, 200, 200, 255)]);
+
+        // Un paquete que se pasa del tamaño de la imagen se recorta
+        let bytes = tga(10, 2, 1, 24, 0x20, &[0x83, 1, 2, 3]);
+        assert_eq!(rgba(&decode_tga(&bytes).unwrap().2), [(3, 2, 1, 255), (3, 2, 1, 255)]);
+    }
+
+    #[test]
+    fn rejects_malformed_tga() {
+        let error_of = |bytes: &[u8]| decode_tga(bytes).unwrap_err();
+        assert_eq!(error_of(&[0; 17]), "truncated TGA header");
+        assert!(error_of(&tga(2, 0, 4, 24, 0, &[])).starts_with("invalid image size 0x4"));
+        assert!(error_of(&tga(2, 4, 0, 24, 0, &[])).starts_with("invalid image size 4x0"));
+        assert!(error_of(&tga(2, 65535, 65535, 32, 0, &[])).starts_with("invalid image size 65535x65535"));
+        assert_eq!(error_of(&tga(1, 1, 1, 8, 0, &[0])), "unsupported TGA image type 1");
+        assert_eq!(error_of(&tga(2, 1, 1, 16, 0, &[0, 0])), "unsupported TGA pixel depth 16");
+        assert_eq!(error_of(&tga(3, 1, 1, 24, 0, &[0, 0, 0])), "unsupported TGA pixel depth 24");
+        assert_eq!(error_of(&tga(2, 2, 1, 24, 0, &[0, 0, 0])), "truncated TGA data");
+        assert_eq!(error_of(&tga(10, 16384, 16384, 24, 0, &[0xff, 0, 0, 0])), "truncated TGA data");
+        assert_eq!(error_of(&tga(10, 2, 1, 24, 0, &[0x01, 0, 0, 0, 0])), "truncated TGA data");
+
+        let mut color_mapped = tga(2, 1, 1, 24, 0, &[0, 0, 0]);
+        color_mapped[1] = 1;
+        assert_eq!(error_of(&color_mapped), "color-mapped TGA is not supported");
+    }
+
+    #[test]
+    fn builds_mipmaps_down_to_one_texel() {
+        let texture = Texture::from_pixels(4, 2, &[Color::white(); 8]).unwrap();
+        assert_eq!(texture.levels.len(), 3);
+        assert_eq!((texture.levels[2].width, texture.levels[2].height), (1, 1));
+
+        assert_eq!(Texture::from_pixels(0, 2, &[]).unwrap_err(), "texture must not be empty");
+        assert_eq!(Texture::from_pixels(2, 2, &[Color::white(); 3]).unwrap_err(), "expected 4 pixels for 2x2, got 3");
+    }
+
+    #[test]
+    fn samples_with_wrap_and_mipmaps() {
+        let (black, white) = (Color::black(), Color::white());
+        let texture = Texture::from_pixels(2, 2, &[black, white, white, black]).unwrap();
+        let nearest = Sampler { filter: Filter::Nearest, ..Sampler::default() };
+
+        assert_eq!(rgba(&[texture.sample(Vec2::new(0.25, 0.25), &nearest)]), [(0, 0, 0, 255)]);
+        assert_eq!(rgba(&[texture.sample(Vec2::new(1.75, 0.25), &nearest)]), [(255, 255, 255, 255)]);
+
+        // Un píxel que abarca toda la textura lee el nivel 1x1, el promedio de los cuatro texeles
+        let far = Vec2::new(1.0, 0.0);
+        assert_eq!(rgba(&[texture.sample_grad(Vec2::new(0.25, 0.25), far, far, &nearest)]), [(128, 128, 128, 255)]);
+        let without_mipmaps = Sampler { mipmaps: false, ..nearest };
+        assert_eq!(rgba(&[texture.sample_grad(Vec2::new(0.25, 0.25), far, far, &without_mipmaps)]), [(0, 0, 0, 255)]);
+    }
+
+    #[test]
+    fn rejects_unknown_extensions() {
+        let err = Texture::load(Path::new("Cargo.toml")).unwrap_err();
+        assert_eq!(err, "Cargo.toml: Unsupported texture format (expected .png, .ppm or .tga)");
+    }
+}
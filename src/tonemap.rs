@@ -0,0 +1,44 @@
+use crate::color::HdrColor;
+
+/// Operador para llevar la radiancia lineal del framebuffer HDR al rango [0, 1] de la pantalla.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToneMapping {
+    // Recorta cada canal a 1.0 (equivale al comportamiento de 8 bits)
+    Clamp,
+    // Reinhard sobre la luminancia, conserva el tono de los colores saturados
+    Reinhard,
+    // Aproximación de la curva fílmica ACES (Narkowicz 2015)
+    Aces,
+}
+
+impl ToneMapping {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "clamp" => Some(ToneMapping::Clamp),
+            "reinhard" => Some(ToneMapping::Reinhard),
+            "aces" => Some(ToneMapping::Aces),
+            _ => None,
+        }
+    }
+
+    pub fn apply(&self, color: HdrColor, exposure: f32) -> HdrColor {
+        let color = color * exposure;
+
+        match self {
+            ToneMapping::Clamp => color,
+            ToneMapping::Reinhard => {
+                let luminance = color.luminance();
+                if luminance <= 0.0 {
+                    return color;
+                }
+                color * (1.0 / (1.0 + luminance))
+            }
+            ToneMapping::Aces => HdrColor::new_rgba(aces(color.r), aces(color.g), aces(color.b), color.a),
+        }
+    }
+}
+
+fn aces(x: f32) -> f32 {
+    let x = x.max(0.0);
+    (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)
+}
@@ -1,70 +1,182 @@
-use nalgebra_glm::{Vec2, Vec3, dot};
+use nalgebra_glm::{Vec2, Vec3};
 use crate::fragment::Fragment;
 use crate::vertex::Vertex;
-use crate::line::line;
 use crate::color::Color;
 
-pub fn _triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> Vec<Fragment> {
+// Bits de precisión sub-píxel usados al ajustar los vértices a coordenadas enteras
+const SUBPIXEL_BITS: i64 = 8;
+const SUBPIXEL_SCALE: f32 = (1 << SUBPIXEL_BITS) as f32;
+const HALF_PIXEL: i64 = 1 << (SUBPIXEL_BITS - 1);
+
+// `clip_rect` (min_x, min_y, max_x, max_y, inclusivos) limita los píxeles generados, p. ej. a un tile.
+// `sample_pattern` son las posiciones de muestra (1/16 de píxel desde el centro) usadas para la cobertura.
+// `tex_coords_slope` solo se calcula con `texture_derivatives`, porque cuesta dos interpolaciones más por fragmento.
+pub fn triangle(
+    v1: &Vertex,
+    v2: &Vertex,
+    v3: &Vertex,
+    clip_rect: (i32, i32, i32, i32),
+    sample_pattern: &[(i32, i32)],
+    texture_derivatives: bool,
+) -> Vec<Fragment> {
     let mut fragments = Vec::new();
 
-    fragments.extend(line(v1, v2));
-    fragments.extend(line(v2, v3));
-    fragments.extend(line(v3, v1));
+    // Vértices ajustados a la rejilla sub-píxel
+    let fa = snap(&v1.transformed_position);
+    let mut fb = snap(&v2.transformed_position);
+    let mut fc = snap(&v3.transformed_position);
+    let (mut v2, mut v3) = (v2, v3);
 
-    fragments
-}
-  
-pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> Vec<Fragment> {
-    let mut fragments = Vec::new();
+    let mut triangle_area = edge_function_fixed(fa, fb, fc);
+    if triangle_area == 0 {
+        return fragments;
+    }
+    // Se ordena el triángulo para que el interior tenga funciones de borde positivas
+    if triangle_area < 0 {
+        std::mem::swap(&mut fb, &mut fc);
+        std::mem::swap(&mut v2, &mut v3);
+        triangle_area = -triangle_area;
+    }
     let (a, b, c) = (v1.transformed_position, v2.transformed_position, v3.transformed_position);
+
+    let min_x = (fa.0.min(fb.0).min(fc.0) >> SUBPIXEL_BITS) as i32;
+    let min_y = (fa.1.min(fb.1).min(fc.1) >> SUBPIXEL_BITS) as i32;
+    let max_x = (fa.0.max(fb.0).max(fc.0) >> SUBPIXEL_BITS) as i32;
+    let max_y = (fa.1.max(fb.1).max(fc.1) >> SUBPIXEL_BITS) as i32;
+    let (min_x, min_y) = (min_x.max(clip_rect.0), min_y.max(clip_rect.1));
+    let (max_x, max_y) = (max_x.min(clip_rect.2), max_y.min(clip_rect.3));
+    if min_x > max_x || min_y > max_y {
+        return fragments;
+    }
   
-    let (min_x, min_y, max_x, max_y) = calculate_bounding_box(&a, &b, &c);
-  
-    let light_dir = Vec3::new(0.0, 0.0, -1.0);
-  
-    let triangle_area = edge_function(&a, &b, &c);
-  
+    // Regla top-left: en bordes que no son superiores ni izquierdos, un punto exactamente
+    // sobre el borde queda fuera, así cada píxel de un borde compartido se dibuja una sola vez
+    let bias1 = fill_rule_bias(fb, fc);
+    let bias2 = fill_rule_bias(fc, fa);
+    let bias3 = fill_rule_bias(fa, fb);
+
+    // 1/w de cada vértice para interpolar con corrección de perspectiva
+    let (inv_w1, inv_w2, inv_w3) = (
+        1.0 / v1.clip_position.w,
+        1.0 / v2.clip_position.w,
+        1.0 / v3.clip_position.w,
+    );
+    let inv_area = 1.0 / triangle_area as f32;
+
+    // Pesos corregidos: se interpola atributo/w y se renormaliza por la suma de 1/w
+    let perspective_weights = |w1: f32, w2: f32, w3: f32| {
+        let (p1, p2, p3) = (w1 * inv_w1, w2 * inv_w2, w3 * inv_w3);
+        let inv_sum = 1.0 / (p1 + p2 + p3);
+        (p1 * inv_sum, p2 * inv_sum, p3 * inv_sum)
+    };
+    // Coordenadas de textura para las funciones de borde (e1, e2, e3)
+    let tex_coords_at = |e1: i64, e2: i64, e3: i64| {
+        let (p1, p2, p3) = perspective_weights(e1 as f32 * inv_area, e2 as f32 * inv_area, e3 as f32 * inv_area);
+        v1.tex_coords * p1 + v2.tex_coords * p2 + v3.tex_coords * p3
+    };
+
+    // Funciones de borde evaluadas en el centro del primer píxel; luego se avanzan de forma incremental
+    let origin = (
+        ((min_x as i64) << SUBPIXEL_BITS) + HALF_PIXEL,
+        ((min_y as i64) << SUBPIXEL_BITS) + HALF_PIXEL,
+    );
+    let mut row1 = edge_function_fixed(fb, fc, origin);
+    let mut row2 = edge_function_fixed(fc, fa, origin);
+    let mut row3 = edge_function_fixed(fa, fb, origin);
+    let (step_x1, step_y1) = edge_steps(fb, fc);
+    let (step_x2, step_y2) = edge_steps(fc, fa);
+    let (step_x3, step_y3) = edge_steps(fa, fb);
+
+    // Diferencia de cada función de borde entre el centro del píxel y cada muestra
+    let sample_offsets: Vec<(i64, i64, i64)> = sample_pattern.iter()
+        .map(|&(sx, sy)| {
+            let (ox, oy) = ((sx as i64) << (SUBPIXEL_BITS - 4), (sy as i64) << (SUBPIXEL_BITS - 4));
+            (
+                (step_x1 * ox + step_y1 * oy) >> SUBPIXEL_BITS,
+                (step_x2 * ox + step_y2 * oy) >> SUBPIXEL_BITS,
+                (step_x3 * ox + step_y3 * oy) >> SUBPIXEL_BITS,
+            )
+        })
+        .collect();
+
+    // La profundidad es un plano en pantalla: su variación por píxel es constante
+    let depth_slope = (
+        (step_x1 as f32 * a.z + step_x2 as f32 * b.z + step_x3 as f32 * c.z) * inv_area,
+        (step_y1 as f32 * a.z + step_y2 as f32 * b.z + step_y3 as f32 * c.z) * inv_area,
+    );
+
     //  Iterar sobre cada píxel en el cuadro delimitador
     for y in min_y..=max_y {
+      let (mut e1, mut e2, mut e3) = (row1, row2, row3);
+
       for x in min_x..=max_x {
-        let point = Vec3::new(x as f32 + 0.5, y as f32 + 0.5, 0.0);
-  
-        // Coordenadas baricéntricas
-        let (w1, w2, w3) = barycentric_coordinates(&point, &a, &b, &c, triangle_area);
+        //verificación de cada muestra del píxel
+        let coverage = sample_offsets.iter()
+            .enumerate()
+            .filter(|(_, (d1, d2, d3))| e1 + d1 + bias1 >= 0 && e2 + d2 + bias2 >= 0 && e3 + d3 + bias3 >= 0)
+            .fold(0u32, |mask, (s, _)| mask | (1 << s));
+
+        if coverage != 0 {
+            // Los atributos se evalúan en el centro del píxel aunque este quede fuera del triángulo
+            // Coordenadas baricéntricas
+            let (w1, w2, w3) = (e1 as f32 * inv_area, e2 as f32 * inv_area, e3 as f32 * inv_area);
 
-        //verificación del punto
-        if w1 >= 0.0 && w1 <= 1.0 &&
-           w2 >= 0.0 && w2 <= 1.0 &&
-           w3 >= 0.0 && w3 <= 1.0 {
-            let normal = v1.transformed_normal * w1 +
-                         v2.transformed_normal * w2 +
-                         v3.transformed_normal * w3;
-            let normal = normal.normalize();
+            let (p1, p2, p3) = perspective_weights(w1, w2, w3);
 
-            let intensity = dot(&normal, &light_dir).max(0.0);
+            let normal = (v1.normal * p1 + v2.normal * p2 + v3.normal * p3).normalize();
+            let world_normal = (v1.transformed_normal * p1 +
+                                v2.transformed_normal * p2 +
+                                v3.transformed_normal * p3).normalize();
 
             let base_color = Color::new(153, 101, 21);
 
+            // La profundidad en pantalla ya es lineal tras la división por `w`
             let depth = w1 * a.z + w2 * b.z + w3 * c.z;
 
-            let vertex_position = v1.position * w1 + v2.position * w2 + v3.position * w3;
+            let vertex_position = v1.position * p1 + v2.position * p2 + v3.position * p3;
+            let tex_coords = v1.tex_coords * p1 + v2.tex_coords * p2 + v3.tex_coords * p3;
+            // Con perspectiva las coordenadas de textura no son lineales en pantalla: se evalúan en los píxeles vecinos
+            let tex_coords_slope = if texture_derivatives {
+                (
+                    tex_coords_at(e1 + step_x1, e2 + step_x2, e3 + step_x3) - tex_coords,
+                    tex_coords_at(e1 + step_y1, e2 + step_y2, e3 + step_y3) - tex_coords,
+                )
+            } else {
+                (Vec2::new(0.0, 0.0), Vec2::new(0.0, 0.0))
+            };
+            let world_position = v1.world_position * p1 + v2.world_position * p2 + v3.world_position * p3;
 
-            fragments.push(Fragment::new(
-                Vec3::new(x as f32, y as f32, 0.0),
-                base_color,
-                depth,      
-                normal,
-                intensity,
-                vertex_position,
-            ));
+            fragments.push(Fragment {
+                tex_coords,
+                world_position,
+                world_normal,
+                coverage,
+                depth_slope,
+                tex_coords_slope,
+                ..Fragment::new(
+                    Vec3::new(x as f32, y as f32, 0.0),
+                    base_color,
+                    depth,
+                    normal,
+                    vertex_position,
+                )
+            });
         }
+
+        e1 += step_x1;
+        e2 += step_x2;
+        e3 += step_x3;
       }
+
+      row1 += step_y1;
+      row2 += step_y2;
+      row3 += step_y3;
     }
 
   fragments
 }
 
-fn calculate_bounding_box(v1: &Vec3, v2: &Vec3, v3: &Vec3) -> (i32, i32, i32, i32) {
+pub fn calculate_bounding_box(v1: &Vec3, v2: &Vec3, v3: &Vec3) -> (i32, i32, i32, i32) {
     let min_x = v1.x.min(v2.x).min(v3.x).floor() as i32;
     let min_y = v1.y.min(v2.y).min(v3.y).floor() as i32;
     let max_x = v1.x.max(v2.x).max(v3.x).ceil() as i32;
@@ -73,14 +185,108 @@ fn calculate_bounding_box(v1: &Vec3, v2: &Vec3, v3: &Vec3) -> (i32, i32, i32, i3
     (min_x, min_y, max_x, max_y)
 }
 
-fn barycentric_coordinates(p: &Vec3, a: &Vec3, b: &Vec3, c: &Vec3, area: f32) -> (f32, f32, f32) {
-    let w1 = edge_function(b, c, p) / area;
-    let w2 = edge_function(c, a, p) / area;
-    let w3 = edge_function(a, b, p) / area;
+fn snap(v: &Vec3) -> (i64, i64) {
+    (
+        (v.x * SUBPIXEL_SCALE).round() as i64,
+        (v.y * SUBPIXEL_SCALE).round() as i64,
+    )
+}
+
+fn edge_function_fixed(a: (i64, i64), b: (i64, i64), c: (i64, i64)) -> i64 {
+    (c.0 - a.0) * (b.1 - a.1) - (c.1 - a.1) * (b.0 - a.0)
+}
 
-    (w1, w2, w3)
+// Incremento de la función de borde al avanzar un píxel en x y en y
+fn edge_steps(a: (i64, i64), b: (i64, i64)) -> (i64, i64) {
+    ((b.1 - a.1) << SUBPIXEL_BITS, -(b.0 - a.0) << SUBPIXEL_BITS)
 }
 
-fn edge_function(a: &Vec3, b: &Vec3, c: &Vec3) -> f32 {
-    (c.x - a.x) * (b.y - a.y) - (c.y - a.y) * (b.x - a.x)
-}
\ No newline at end of file
+// Con y hacia abajo y el interior a la izquierda del recorrido, un borde superior es
+// horizontal y va hacia -x, y un borde izquierdo baja (y creciente)
+fn fill_rule_bias(a: (i64, i64), b: (i64, i64)) -> i64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let is_top = dy == 0 && dx < 0;
+    let is_left = dy > 0;
+    if is_top || is_left { 0 } else { -1 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framebuffer::sample_pattern;
+
+    fn vertex(x: f32, y: f32) -> Vertex {
+        Vertex::new(Vec3::new(x, y, 0.0), Vec3::new(0.0, 0.0, 1.0), Vec2::new(0.0, 0.0))
+    }
+
+    // Veces que cada muestra de una rejilla de 20x20 píxeles queda cubierta por los triángulos
+    fn coverage_counts(triangles: &[[(f32, f32); 3]], pattern: &[(i32, i32)]) -> Vec<u32> {
+        let mut counts = vec![0; 20 * 20 * pattern.len()];
+        for [a, b, c] in triangles {
+            let (v1, v2, v3) = (vertex(a.0, a.1), vertex(b.0, b.1), vertex(c.0, c.1));
+            for fragment in triangle(&v1, &v2, &v3, (0, 0, 19, 19), pattern, false) {
+                let (x, y) = (fragment.position.x as usize, fragment.position.y as usize);
+                for s in (0..pattern.len()).filter(|s| fragment.coverage & (1 << s) != 0) {
+                    counts[(y * 20 + x) * pattern.len() + s] += 1;
+                }
+            }
+        }
+        counts
+    }
+
+    // Cada muestra dentro del polígono convexo `outline` se cubre exactamente una vez y las de afuera ninguna.
+    // Las muestras sobre el contorno exterior pueden caer de cualquier lado, pero nunca dos veces.
+    fn assert_covered_once(triangles: &[[(f32, f32); 3]], outline: &[(f32, f32)]) {
+        for samples in [1, 4] {
+            let pattern = sample_pattern(samples).unwrap();
+            let counts = coverage_counts(triangles, pattern);
+            for (index, &count) in counts.iter().enumerate() {
+                let (pixel, s) = (index / samples, index % samples);
+                let x = (pixel % 20) as f32 + 0.5 + pattern[s].0 as f32 / 16.0;
+                let y = (pixel / 20) as f32 + 0.5 + pattern[s].1 as f32 / 16.0;
+
+                let distances: Vec<f32> = outline.iter()
+                    .zip(outline.iter().cycle().skip(1))
+                    .map(|(a, b)| ((b.0 - a.0) * (y - a.1) - (b.1 - a.1) * (x - a.0)) / (b.0 - a.0).hypot(b.1 - a.1))
+                    .collect();
+                let inside = distances.iter().all(|&d| d > 1e-3);
+                let outside = distances.iter().any(|&d| d < -1e-3);
+
+                assert!(count <= 1, "sample ({}, {}) written {} times with {}x MSAA", x, y, count, samples);
+                if inside {
+                    assert_eq!(count, 1, "sample ({}, {}) not covered with {}x MSAA", x, y, samples);
+                } else if outside {
+                    assert_eq!(count, 0, "sample ({}, {}) outside the shape covered with {}x MSAA", x, y, samples);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn quad_split_along_diagonal_covers_each_sample_once() {
+        // Esquinas fuera de la rejilla de muestras, y otro cuadrado con la diagonal sobre centros de píxel
+        let quad = [(2.3, 1.7), (13.9, 3.1), (12.2, 14.6), (1.1, 11.8)];
+        assert_covered_once(&[[quad[0], quad[1], quad[2]], [quad[0], quad[2], quad[3]]], &quad);
+
+        let square = [(2.5, 2.5), (12.5, 2.5), (12.5, 12.5), (2.5, 12.5)];
+        assert_covered_once(&[[square[0], square[1], square[2]], [square[2], square[3], square[0]]], &square);
+    }
+
+    #[test]
+    fn fan_around_shared_vertex_covers_each_sample_once() {
+        // Vértice compartido sobre el centro de un píxel; los radios horizontales, verticales y diagonales
+        // pasan por centros de píxel, y la mitad de los triángulos se recorre en el otro sentido
+        let center = (9.5, 9.5);
+        let outline = [
+            (9.5, 1.5), (15.5, 3.5), (17.5, 9.5), (15.5, 15.5),
+            (9.5, 17.5), (3.5, 15.5), (1.5, 9.5), (3.5, 3.5),
+        ];
+        let fan: Vec<[(f32, f32); 3]> = (0..outline.len())
+            .map(|i| {
+                let (a, b) = (outline[i], outline[(i + 1) % outline.len()]);
+                if i % 2 == 0 { [center, a, b] } else { [b, a, center] }
+            })
+            .collect();
+        assert_covered_once(&fan, &outline);
+    }
+}
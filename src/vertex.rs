@@ -1,4 +1,4 @@
-use nalgebra_glm::{Vec2, Vec3};
+use nalgebra_glm::{Vec2, Vec3, Vec4};
 use crate::color::Color;
 
 #[derive(Clone, Debug)]
@@ -7,6 +7,9 @@ pub struct Vertex {
   pub normal: Vec3,
   pub tex_coords: Vec2,
   pub color: Color,
+  pub clip_position: Vec4,
+  // Posición en espacio de mundo (después de la matriz de modelo)
+  pub world_position: Vec3,
   pub transformed_position: Vec3,
   pub transformed_normal: Vec3,
 }
@@ -18,26 +21,26 @@ impl Vertex {
       normal,
       tex_coords,
       color: Color::black(),
+      clip_position: Vec4::new(position.x, position.y, position.z, 1.0),
+      world_position: position,
       transformed_position: position,
       transformed_normal: normal,
     }
   }
 
-  pub fn new_with_color(position: Vec3, color: Color) -> Self {
+  // Interpola todos los atributos entre dos vértices (usado al recortar triángulos)
+  pub fn lerp(&self, other: &Vertex, t: f32) -> Vertex {
     Vertex {
-      position,
-      normal: Vec3::new(0.0, 0.0, 0.0),
-      tex_coords: Vec2::new(0.0, 0.0),
-      color,
-      transformed_position: Vec3::new(0.0, 0.0, 0.0),
-      transformed_normal: Vec3::new(0.0, 0.0, 0.0),
+      position: self.position.lerp(&other.position, t),
+      normal: self.normal.lerp(&other.normal, t),
+      tex_coords: self.tex_coords.lerp(&other.tex_coords, t),
+      color: self.color.lerp(&other.color, t),
+      clip_position: self.clip_position.lerp(&other.clip_position, t),
+      world_position: self.world_position.lerp(&other.world_position, t),
+      transformed_position: self.transformed_position.lerp(&other.transformed_position, t),
+      transformed_normal: self.transformed_normal.lerp(&other.transformed_normal, t),
     }
   }
-
-  pub fn set_transformed(&mut self, position: Vec3, normal: Vec3) {
-    self.transformed_position = position;
-    self.transformed_normal = normal;
-  }
 }
 
 impl Default for Vertex {
@@ -47,8 +50,10 @@ impl Default for Vertex {
       normal: Vec3::new(0.0, 1.0, 0.0),
       tex_coords: Vec2::new(0.0, 0.0),
       color: Color::black(),
+      clip_position: Vec4::new(0.0, 0.0, 0.0, 1.0),
+      world_position: Vec3::new(0.0, 0.0, 0.0),
       transformed_position: Vec3::new(0.0, 0.0, 0.0),
       transformed_normal: Vec3::new(0.0, 1.0, 0.0),
     }
   }
-}
\ No newline at end of file
+}